@@ -0,0 +1,229 @@
+use crate::validation::{currency_code, not_blank};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+// Stored as the Postgres enum `course_level`. The capitalized spellings used
+// before the column was typed are still accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "course_level", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CourseLevel {
+    #[serde(alias = "Beginner")]
+    Beginner,
+    #[serde(alias = "Intermediate")]
+    Intermediate,
+    #[serde(alias = "Advanced")]
+    Advanced,
+}
+
+// Stored as the Postgres enum `course_format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "course_format", rename_all = "snake_case")
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CourseFormat {
+    Ebook,
+    Video,
+    Audio,
+    Live,
+    InPerson,
+}
+
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+// Prices are kept in cents of course_currency, an ISO 4217 code of a currency
+// with two decimals, and durations in minutes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Course {
+    pub course_id: i32,
+    pub tutor_id: i32,
+    pub course_name: String,
+    pub course_description: Option<String>,
+    pub course_format: Option<CourseFormat>,
+    pub course_structure: Option<String>,
+    pub course_duration_minutes: Option<i32>,
+    pub course_price_cents: Option<i32>,
+    pub course_currency: String,
+    pub course_lenguage: Option<String>,
+    pub course_level: Option<CourseLevel>,
+    pub course_capacity: Option<i32>,
+    pub posted_time: Option<NaiveDateTime>,
+}
+
+// Limits follow the column sizes of ezy_course_c6
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct CreateCourse {
+    pub tutor_id: i32,
+    #[validate(length(max = 140), custom = "not_blank")]
+    pub course_name: String,
+    #[validate(length(max = 2000))]
+    pub course_description: Option<String>,
+    pub course_format: Option<CourseFormat>,
+    #[validate(length(max = 200))]
+    pub course_structure: Option<String>,
+    #[validate(range(min = 1))]
+    pub course_duration_minutes: Option<i32>,
+    #[validate(range(min = 0))]
+    pub course_price_cents: Option<i32>,
+    #[validate(custom = "currency_code")]
+    pub course_currency: Option<String>,
+    #[validate(length(max = 30))]
+    pub course_lenguage: Option<String>,
+    pub course_level: Option<CourseLevel>,
+    #[validate(range(min = 0))]
+    pub course_capacity: Option<i32>,
+}
+
+// A course as the web app receives it, for the tutor who is signed in. It is
+// checked by the tutor web service once posted as a CreateCourse.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewCourse {
+    pub course_name: String,
+    pub course_description: Option<String>,
+    pub course_format: Option<CourseFormat>,
+    pub course_structure: Option<String>,
+    pub course_duration_minutes: Option<i32>,
+    pub course_price_cents: Option<i32>,
+    pub course_currency: Option<String>,
+    pub course_lenguage: Option<String>,
+    pub course_level: Option<CourseLevel>,
+    pub course_capacity: Option<i32>,
+}
+
+impl NewCourse {
+    pub fn for_tutor(self, tutor_id: i32) -> CreateCourse {
+        CreateCourse {
+            tutor_id,
+            course_name: self.course_name,
+            course_description: self.course_description,
+            course_format: self.course_format,
+            course_structure: self.course_structure,
+            course_duration_minutes: self.course_duration_minutes,
+            course_price_cents: self.course_price_cents,
+            course_currency: self.course_currency,
+            course_lenguage: self.course_lenguage,
+            course_level: self.course_level,
+            course_capacity: self.course_capacity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Validate)]
+pub struct UpdateCourse {
+    #[validate(length(max = 140), custom = "not_blank")]
+    pub course_name: Option<String>,
+    #[validate(length(max = 2000))]
+    pub course_description: Option<String>,
+    pub course_format: Option<CourseFormat>,
+    #[validate(length(max = 200))]
+    pub course_structure: Option<String>,
+    #[validate(range(min = 1))]
+    pub course_duration_minutes: Option<i32>,
+    #[validate(range(min = 0))]
+    pub course_price_cents: Option<i32>,
+    #[validate(custom = "currency_code")]
+    pub course_currency: Option<String>,
+    #[validate(length(max = 30))]
+    pub course_lenguage: Option<String>,
+    pub course_level: Option<CourseLevel>,
+    #[validate(range(min = 0))]
+    pub course_capacity: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    #[test]
+    fn course_round_trip() {
+        let course = Course {
+            course_id: 7,
+            tutor_id: 1,
+            course_name: "Rust for beginners".into(),
+            course_description: None,
+            course_format: Some(CourseFormat::InPerson),
+            course_structure: None,
+            course_duration_minutes: Some(90),
+            // The service answers null for courses without a price
+            course_price_cents: None,
+            course_currency: DEFAULT_CURRENCY.into(),
+            course_lenguage: Some("English".into()),
+            course_level: Some(CourseLevel::Beginner),
+            course_capacity: None,
+            posted_time: NaiveDate::from_ymd_opt(2022, 6, 1)
+                .and_then(|day| day.and_hms_opt(10, 30, 0)),
+        };
+
+        let value = serde_json::to_value(&course).unwrap();
+        assert_eq!(value["course_format"], "in_person");
+        assert_eq!(value["course_level"], "beginner");
+        assert_eq!(value["course_price_cents"], json!(null));
+        assert_eq!(value["posted_time"], "2022-06-01T10:30:00");
+        assert_eq!(serde_json::from_value::<Course>(value).unwrap(), course);
+    }
+
+    #[test]
+    fn new_course_round_trip() {
+        let new_course: NewCourse = serde_json::from_value(json!({
+            "course_name": "Rust for beginners",
+            "course_description": "Ownership and borrowing",
+            "course_format": "video",
+            "course_duration_minutes": null,
+            "course_structure": null,
+            "course_price_cents": 2500,
+            "course_currency": "EUR",
+            "course_lenguage": "English",
+            "course_level": "Beginner",
+            "course_capacity": null
+        }))
+        .unwrap();
+        assert_eq!(new_course.course_level, Some(CourseLevel::Beginner));
+
+        let create_course = new_course.for_tutor(1);
+        assert!(create_course.validate().is_ok());
+        let value = serde_json::to_value(&create_course).unwrap();
+        assert_eq!(value["tutor_id"], 1);
+        assert_eq!(value["course_level"], "beginner");
+        assert_eq!(
+            serde_json::from_value::<CreateCourse>(value).unwrap(),
+            create_course
+        );
+    }
+
+    #[test]
+    fn update_course_round_trip() {
+        let update_course = UpdateCourse {
+            course_price_cents: Some(1000),
+            course_format: Some(CourseFormat::Ebook),
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&update_course).unwrap();
+        assert_eq!(value["course_format"], "ebook");
+        assert_eq!(value["course_name"], json!(null));
+        assert_eq!(
+            serde_json::from_value::<UpdateCourse>(value).unwrap(),
+            update_course
+        );
+        // Fields left out are not updated
+        let empty: UpdateCourse = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(empty, UpdateCourse::default());
+    }
+
+    #[test]
+    fn unknown_levels_and_formats_are_refused() {
+        assert!(serde_json::from_value::<CourseLevel>(json!("expert")).is_err());
+        assert!(serde_json::from_value::<CourseFormat>(json!("pdf")).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+// Requests and responses of the tutor web service, shared by the service and
+// the web app that calls it
+pub mod course;
+pub mod page;
+pub mod tutor;
+pub mod validation;
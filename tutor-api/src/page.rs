@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+// One page of a listing. `next_cursor` is the offset of the next page, unset
+// on the last one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub next_cursor: Option<i64>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: i64, limit: i64, offset: i64) -> Self {
+        let next_offset = offset + items.len() as i64;
+        let next_cursor = if !items.is_empty() && next_offset < total {
+            Some(next_offset)
+        } else {
+            None
+        };
+
+        Page {
+            items,
+            total,
+            limit,
+            offset,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn page_round_trip() {
+        let page = Page::new(vec![1, 2], 5, 2, 0);
+        let value = serde_json::to_value(&page).unwrap();
+        assert_eq!(
+            value,
+            json!({"items": [1, 2], "total": 5, "limit": 2, "offset": 0, "next_cursor": 2})
+        );
+        assert_eq!(serde_json::from_value::<Page<i32>>(value).unwrap(), page);
+
+        let last = Page::new(vec![5], 5, 2, 4);
+        assert_eq!(last.next_cursor, None);
+    }
+}
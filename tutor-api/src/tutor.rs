@@ -0,0 +1,68 @@
+use crate::validation::not_blank;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tutor {
+    pub tutor_id: i32,
+    pub tutor_name: String,
+    pub tutor_pic_url: String,
+    pub tutor_profile: String,
+}
+
+// Limits follow the column sizes of ezy_tutor_c6
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct NewTutor {
+    #[validate(length(max = 200), custom = "not_blank")]
+    pub tutor_name: String,
+    #[validate(length(max = 200), url)]
+    pub tutor_pic_url: String,
+    #[validate(length(max = 200))]
+    pub tutor_profile: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct UpdateTutor {
+    #[validate(length(max = 200), custom = "not_blank")]
+    pub tutor_name: Option<String>,
+    #[validate(length(max = 200), url)]
+    pub tutor_pic_url: Option<String>,
+    #[validate(length(max = 200))]
+    pub tutor_profile: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tutor_round_trip() {
+        let body = json!({
+            "tutor_id": 1,
+            "tutor_name": "Merlene",
+            "tutor_pic_url": "http://s3.amazon.aws.com/pic1",
+            "tutor_profile": "Merlene is an experienced finance professional"
+        });
+
+        let tutor: Tutor = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(tutor.tutor_name, "Merlene");
+        assert_eq!(serde_json::to_value(&tutor).unwrap(), body);
+    }
+
+    #[test]
+    fn new_tutor_round_trip() {
+        let new_tutor = NewTutor {
+            tutor_name: "Frank".into(),
+            tutor_pic_url: "http://s3.amazon.aws.com/pic2".into(),
+            tutor_profile: "Frank is an expert nuclear engineer".into(),
+        };
+        assert!(new_tutor.validate().is_ok());
+
+        let value = serde_json::to_value(&new_tutor).unwrap();
+        assert_eq!(
+            serde_json::from_value::<NewTutor>(value).unwrap(),
+            new_tutor
+        );
+    }
+}
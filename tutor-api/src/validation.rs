@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+// One rejected field of a request body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub reason: String,
+}
+
+// Custom checks used by the `#[validate(custom = ...)]` attributes of the
+// request models. The built-in validators cover lengths, ranges and urls.
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        let mut err = ValidationError::new("blank");
+        err.message = Some("must not be blank".into());
+        return Err(err);
+    }
+    Ok(())
+}
+
+// ISO 4217 currencies whose minor unit is not a hundredth, e.g. JPY with no
+// decimals or KWD with three. Must match the course_currency_iso constraint.
+const CURRENCIES_WITHOUT_CENTS: [&str; 26] = [
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND",
+    "VUV", "XAF", "XOF", "XPF", "BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND", "CLF", "UYW",
+];
+
+// Three uppercase letters, as in ISO 4217 (USD, EUR, ...), of a currency with
+// cents, as prices are kept in cents
+pub fn currency_code(value: &str) -> Result<(), ValidationError> {
+    if value.len() != 3 || !value.bytes().all(|b| b.is_ascii_uppercase()) {
+        let mut err = ValidationError::new("currency");
+        err.message = Some("must be a three letter ISO 4217 currency code".into());
+        return Err(err);
+    }
+    if CURRENCIES_WITHOUT_CENTS.contains(&value) {
+        let mut err = ValidationError::new("currency");
+        err.message = Some("must be a currency with two decimals".into());
+        return Err(err);
+    }
+    Ok(())
+}
+
+// The fields rejected by `validate()`, sorted by name
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut field_errors: Vec<FieldError> = errors
+        .errors()
+        .iter()
+        .flat_map(|(field, kind)| match kind {
+            ValidationErrorsKind::Field(errors) => errors
+                .iter()
+                .map(|err| FieldError {
+                    field: field.to_string(),
+                    reason: validation_reason(err),
+                })
+                .collect(),
+            // The request models have no nested structs or lists
+            _ => Vec::new(),
+        })
+        .collect();
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+    field_errors
+}
+
+fn validation_reason(err: &ValidationError) -> String {
+    if let Some(message) = &err.message {
+        return message.to_string();
+    }
+
+    // Range bounds are stored as floats, show whole numbers without ".0"
+    let param = |name: &str| {
+        err.params.get(name).map(|value| match value.as_f64() {
+            Some(number) if number.fract() == 0.0 => format!("{}", number as i64),
+            _ => value.to_string(),
+        })
+    };
+    match (err.code.as_ref(), param("min"), param("max")) {
+        ("length", None, Some(max)) => format!("must be at most {} characters long", max),
+        ("length", Some(min), None) => format!("must be at least {} characters long", min),
+        ("range", Some(min), None) => format!("must be at least {}", min),
+        ("range", None, Some(max)) => format!("must be at most {}", max),
+        ("range", Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+        ("url", _, _) => "must be a valid URL".into(),
+        ("email", _, _) => "must be a valid email address".into(),
+        (code, _, _) => format!("is invalid ({})", code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::UpdateCourse;
+    use validator::Validate;
+
+    #[test]
+    fn currencies_need_cents() {
+        assert!(currency_code("EUR").is_ok());
+        assert!(currency_code("eur").is_err());
+        let err = currency_code("JPY").unwrap_err();
+        assert_eq!(
+            err.message.as_deref(),
+            Some("must be a currency with two decimals")
+        );
+        assert!(currency_code("KWD").is_err());
+    }
+
+    #[test]
+    fn field_errors_are_sorted_and_explained() {
+        let update_course = UpdateCourse {
+            course_name: Some(" ".into()),
+            course_duration_minutes: Some(0),
+            course_currency: Some("usd".into()),
+            ..Default::default()
+        };
+        let errors = update_course.validate().unwrap_err();
+
+        assert_eq!(
+            field_errors(&errors),
+            [
+                FieldError {
+                    field: "course_currency".into(),
+                    reason: "must be a three letter ISO 4217 currency code".into(),
+                },
+                FieldError {
+                    field: "course_duration_minutes".into(),
+                    reason: "must be at least 1".into(),
+                },
+                FieldError {
+                    field: "course_name".into(),
+                    reason: "must not be blank".into(),
+                },
+            ]
+        );
+    }
+}
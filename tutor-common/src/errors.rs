@@ -0,0 +1,362 @@
+use crate::metrics;
+use actix_web::{error, http::header, http::StatusCode, HttpResponse};
+use serde::Serialize;
+use sqlx::error::Error as SQLxError;
+use sqlx::postgres::PgDatabaseError;
+use std::fmt;
+use tutor_api::validation::field_errors;
+use validator::ValidationErrors;
+
+pub use tutor_api::validation::FieldError;
+
+// The error type of the tutor web service and the web app. The String of
+// each variant is the detail that gets logged; what the client sees is
+// `message()` together with the stable `code()`.
+#[derive(Debug, Serialize)]
+pub enum EzyTutorError {
+    DBError(String),
+    ActixError(String),
+    TeraError(String),
+    NotFound(String),
+    InvalidInput(String),
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    ValidationFailed(Vec<FieldError>),
+    BackendError(String),
+}
+
+// Machine-readable error codes, part of the API contract: add new codes but
+// never rename existing ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    DatabaseError,
+    InternalError,
+    NotFound,
+    InvalidInput,
+    Conflict,
+    Unauthorized,
+    Forbidden,
+    ValidationFailed,
+    BackendUnavailable,
+}
+
+impl ErrorCode {
+    // The code as it appears in response bodies, e.g. NOT_FOUND
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::InvalidInput => "INVALID_INPUT",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::BackendUnavailable => "BACKEND_UNAVAILABLE",
+        }
+    }
+
+    // Code for error responses that were not raised as an EzyTutorError
+    pub fn for_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::ValidationFailed,
+            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => ErrorCode::BackendUnavailable,
+            status if status.is_client_error() => ErrorCode::InvalidInput,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+// Body of every error response, e.g.
+// `{"code":"NOT_FOUND","message":"Course id not found","request_id":"..."}`
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    pub fn into_http_response(self, status: StatusCode) -> HttpResponse {
+        let mut response = HttpResponse::build(status);
+        if self.code == ErrorCode::Unauthorized {
+            response.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+
+        response.json(self)
+    }
+}
+
+impl EzyTutorError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            EzyTutorError::DBError(_) => ErrorCode::DatabaseError,
+            EzyTutorError::ActixError(_) | EzyTutorError::TeraError(_) => ErrorCode::InternalError,
+            EzyTutorError::NotFound(_) => ErrorCode::NotFound,
+            EzyTutorError::InvalidInput(_) => ErrorCode::InvalidInput,
+            EzyTutorError::Conflict(_) => ErrorCode::Conflict,
+            EzyTutorError::Unauthorized(_) => ErrorCode::Unauthorized,
+            EzyTutorError::Forbidden(_) => ErrorCode::Forbidden,
+            EzyTutorError::ValidationFailed(_) => ErrorCode::ValidationFailed,
+            EzyTutorError::BackendError(_) => ErrorCode::BackendUnavailable,
+        }
+    }
+
+    // The message shown to clients. Details of server side failures are only
+    // logged.
+    pub fn message(&self) -> String {
+        match self {
+            EzyTutorError::DBError(_) => "Database error".into(),
+            EzyTutorError::ActixError(_) | EzyTutorError::TeraError(_) => {
+                "Internal server error".into()
+            }
+            EzyTutorError::BackendError(_) => "Tutor web service is not available".into(),
+            EzyTutorError::ValidationFailed(_) => "Validation failed".into(),
+            EzyTutorError::NotFound(msg)
+            | EzyTutorError::InvalidInput(msg)
+            | EzyTutorError::Conflict(msg)
+            | EzyTutorError::Unauthorized(msg)
+            | EzyTutorError::Forbidden(msg) => msg.clone(),
+        }
+    }
+
+    pub fn to_response(&self, request_id: Option<&str>) -> ErrorResponse {
+        let fields = match self {
+            EzyTutorError::ValidationFailed(fields) => fields.clone(),
+            _ => Vec::new(),
+        };
+
+        ErrorResponse {
+            code: self.code(),
+            message: self.message(),
+            fields,
+            request_id: request_id.map(str::to_string),
+        }
+    }
+}
+
+impl error::ResponseError for EzyTutorError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            EzyTutorError::DBError(_)
+            | EzyTutorError::ActixError(_)
+            | EzyTutorError::TeraError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            EzyTutorError::NotFound(_) => StatusCode::NOT_FOUND,
+            EzyTutorError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            EzyTutorError::Conflict(_) => StatusCode::CONFLICT,
+            EzyTutorError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            EzyTutorError::Forbidden(_) => StatusCode::FORBIDDEN,
+            EzyTutorError::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            EzyTutorError::BackendError(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.to_response(None)
+            .into_http_response(self.status_code())
+    }
+}
+
+impl std::error::Error for EzyTutorError {}
+
+impl fmt::Display for EzyTutorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.code().as_str();
+        match self {
+            EzyTutorError::ValidationFailed(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|field| format!("{} {}", field.field, field.reason))
+                    .collect();
+                write!(f, "{}: {}", code, fields.join(", "))
+            }
+            EzyTutorError::DBError(msg)
+            | EzyTutorError::ActixError(msg)
+            | EzyTutorError::TeraError(msg)
+            | EzyTutorError::NotFound(msg)
+            | EzyTutorError::InvalidInput(msg)
+            | EzyTutorError::Conflict(msg)
+            | EzyTutorError::Unauthorized(msg)
+            | EzyTutorError::Forbidden(msg)
+            | EzyTutorError::BackendError(msg) => write!(f, "{}: {}", code, msg),
+        }
+    }
+}
+
+impl From<actix_web::error::Error> for EzyTutorError {
+    fn from(err: actix_web::error::Error) -> Self {
+        EzyTutorError::ActixError(err.to_string())
+    }
+}
+
+// Columns guarded by the check constraints of the migrations
+const CHECKED_FIELDS: &[(&str, &str)] = &[
+    ("course_name_not_blank", "course_name"),
+    ("course_duration_positive", "course_duration_minutes"),
+    ("course_price_not_negative", "course_price_cents"),
+    ("course_currency_iso", "course_currency"),
+    ("course_capacity_not_negative", "course_capacity"),
+];
+
+fn checked_field(constraint: &str) -> &str {
+    CHECKED_FIELDS
+        .iter()
+        .find(|(name, _)| *name == constraint)
+        .map_or(constraint, |(_, field)| field)
+}
+
+// Constraint violations are caused by the request rather than by the server,
+// so they are reported as client errors naming the constraint or column.
+// Everything else stays a DBError. Either way the error is counted in the
+// db_query_errors_total metric.
+impl From<SQLxError> for EzyTutorError {
+    fn from(err: SQLxError) -> Self {
+        let error = database_error(err, &[]);
+        metrics::count_query_error(error.code());
+        error
+    }
+}
+
+impl EzyTutorError {
+    // For statements deleting rows, where a foreign key violation means the
+    // row is still referenced. Postgres uses the same code for a referenced
+    // row that is missing, and only its localized message tells them apart,
+    // so the statement decides.
+    pub fn from_delete(err: SQLxError) -> Self {
+        let error = match err.as_database_error() {
+            Some(db_err) if db_err.code().as_deref() == Some("23503") => {
+                let db_err = db_err.downcast_ref::<PgDatabaseError>();
+                EzyTutorError::Conflict(format!(
+                    "Record is still referenced, see constraint {}",
+                    db_err.constraint().unwrap_or("unknown")
+                ))
+            }
+            _ => database_error(err, &[]),
+        };
+        metrics::count_query_error(error.code());
+        error
+    }
+
+    // For statements writing text. Postgres does not name the column a value
+    // was too long for, so the statement lists the text it writes as
+    // (column, value, length of the column) for the error to name it.
+    pub fn from_write(err: SQLxError, text: &[(&str, &str, usize)]) -> Self {
+        let error = database_error(err, text);
+        metrics::count_query_error(error.code());
+        error
+    }
+}
+
+fn database_error(err: SQLxError, text: &[(&str, &str, usize)]) -> EzyTutorError {
+    let db_err = match err.as_database_error() {
+        Some(db_err) => db_err.downcast_ref::<PgDatabaseError>(),
+        None => return EzyTutorError::DBError(err.to_string()),
+    };
+    let constraint = db_err.constraint().unwrap_or("unknown").to_string();
+
+    match db_err.code() {
+        // foreign_key_violation of an insert or update, deletes go through
+        // from_delete
+        "23503" => EzyTutorError::NotFound(format!(
+            "Referenced record does not exist, see constraint {}: {}",
+            constraint,
+            db_err.detail().unwrap_or_default()
+        )),
+        // unique_violation
+        "23505" => EzyTutorError::Conflict(format!(
+            "Record already exists, see constraint {}: {}",
+            constraint,
+            db_err.detail().unwrap_or_default()
+        )),
+        // not_null_violation
+        "23502" => EzyTutorError::ValidationFailed(vec![FieldError {
+            field: db_err.column().unwrap_or("unknown").to_string(),
+            reason: "must not be null".into(),
+        }]),
+        // check_violation
+        "23514" => EzyTutorError::ValidationFailed(vec![FieldError {
+            field: checked_field(&constraint).to_string(),
+            reason: format!("violates check constraint {}", constraint),
+        }]),
+        // string_data_right_truncation, the column is only known when the
+        // statement listed its text
+        "22001" => EzyTutorError::ValidationFailed(vec![match text
+            .iter()
+            .find(|(_, value, len)| value.chars().count() > *len)
+        {
+            Some((column, _, len)) => FieldError {
+                field: column.to_string(),
+                reason: format!("must be at most {} characters long", len),
+            },
+            None => FieldError {
+                field: "unknown".into(),
+                reason: db_err.message().to_string(),
+            },
+        }]),
+        _ => EzyTutorError::DBError(err.to_string()),
+    }
+}
+
+impl From<ValidationErrors> for EzyTutorError {
+    fn from(errors: ValidationErrors) -> Self {
+        EzyTutorError::ValidationFailed(field_errors(&errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::ResponseError;
+
+    #[test]
+    fn display_shows_code_and_detail() {
+        let err = EzyTutorError::DBError("connection refused".into());
+        assert_eq!(err.to_string(), "DATABASE_ERROR: connection refused");
+
+        let err = EzyTutorError::ValidationFailed(vec![FieldError {
+            field: "course_name".into(),
+            reason: "must not be blank".into(),
+        }]);
+        assert_eq!(
+            err.to_string(),
+            "VALIDATION_FAILED: course_name must not be blank"
+        );
+    }
+
+    #[test]
+    fn codes_match_their_serialized_form() {
+        for err in [
+            EzyTutorError::DBError(String::new()),
+            EzyTutorError::TeraError(String::new()),
+            EzyTutorError::NotFound(String::new()),
+            EzyTutorError::ValidationFailed(Vec::new()),
+            EzyTutorError::BackendError(String::new()),
+        ] {
+            let code = serde_json::to_value(err.code()).unwrap();
+            assert_eq!(code, err.code().as_str());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn server_errors_hide_their_detail() {
+        let err = EzyTutorError::DBError("password authentication failed".into());
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = to_bytes(err.error_response().into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"code": "DATABASE_ERROR", "message": "Database error"})
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use actix_web::HttpResponse;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::postgres::PgPool;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+// Result of checking one dependency. Details are reported as they are, e.g.
+// the pool stats of the database.
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl ComponentHealth {
+    pub fn up(details: Value) -> Self {
+        ComponentHealth {
+            status: HealthStatus::Up,
+            error: None,
+            details: into_map(details),
+        }
+    }
+
+    pub fn down(error: impl ToString, details: Value) -> Self {
+        ComponentHealth {
+            status: HealthStatus::Down,
+            error: Some(error.to_string()),
+            details: into_map(details),
+        }
+    }
+}
+
+fn into_map(details: Value) -> Map<String, Value> {
+    match details {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+// Body of /health/ready, e.g.
+// `{"status":"down","components":{"database":{"status":"up",...},...}}`
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+impl HealthReport {
+    // The report is up only when every component is
+    pub fn new(components: BTreeMap<&'static str, ComponentHealth>) -> Self {
+        let status = if components
+            .values()
+            .all(|component| component.status == HealthStatus::Up)
+        {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+
+        HealthReport { status, components }
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        match self.status {
+            HealthStatus::Up => HttpResponse::Ok().json(self),
+            HealthStatus::Down => HttpResponse::ServiceUnavailable().json(self),
+        }
+    }
+}
+
+// Answer of /health/live: the process is running and serving requests. It
+// checks no dependencies, so that a database outage does not get the app
+// restarted.
+pub fn live() -> HttpResponse {
+    HttpResponse::Ok().json(json!({"status": HealthStatus::Up}))
+}
+
+// Pings the database through the pool
+pub async fn check_database(pool: &PgPool) -> ComponentHealth {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    let details = json!({
+        "pool_size": size,
+        "pool_idle": idle,
+        "pool_in_use": size.saturating_sub(idle),
+    });
+
+    match sqlx::query("SELECT 1").execute(pool).await {
+        Ok(_) => ComponentHealth::up(details),
+        Err(err) => ComponentHealth::down(err, details),
+    }
+}
+
+// Checks that every migration of `migrator` has been applied and that none
+// was left half applied
+pub async fn check_migrations(pool: &PgPool, migrator: &Migrator) -> ComponentHealth {
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => return ComponentHealth::down(err, Value::Null),
+    };
+    let dirty = match conn.dirty_version().await {
+        Ok(dirty) => dirty,
+        Err(err) => return ComponentHealth::down(err, Value::Null),
+    };
+    let applied: HashSet<i64> = match conn.list_applied_migrations().await {
+        Ok(applied) => applied
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect(),
+        Err(err) => return ComponentHealth::down(err, Value::Null),
+    };
+
+    let pending: Vec<i64> = migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect();
+    let details = json!({ "pending": pending });
+
+    if let Some(version) = dirty {
+        ComponentHealth::down(format!("migration {} is incomplete", version), details)
+    } else if !pending.is_empty() {
+        ComponentHealth::down("migrations are pending", details)
+    } else {
+        ComponentHealth::up(details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+
+    #[test]
+    fn failing_component_is_unavailable() {
+        let report = HealthReport::new(BTreeMap::from([
+            ("database", ComponentHealth::up(json!({"pool_size": 1}))),
+            (
+                "backend",
+                ComponentHealth::down("connection refused", Value::Null),
+            ),
+        ]));
+        assert_eq!(report.status, HealthStatus::Down);
+
+        let resp = report.into_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = resp.into_body().try_into_bytes().unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "status": "down",
+                "components": {
+                    "backend": {"status": "down", "error": "connection refused"},
+                    "database": {"status": "up", "pool_size": 1}
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,9 @@
+// Code shared by the tutor web service and the server-side rendered web app
+pub mod errors;
+pub mod health;
+pub mod metrics;
+pub mod migrate;
+pub mod request_id;
+pub mod settings;
+pub mod shutdown;
+pub mod telemetry;
@@ -0,0 +1,179 @@
+use crate::errors::ErrorCode;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{http::header, Error, HttpResponse};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use sqlx::postgres::PgPool;
+use std::time::Instant;
+
+// Metrics are kept in the default prometheus registry and rendered by
+// `render`, which both apps serve at /metrics.
+
+static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests handled, by method, route and status",
+        &["method", "route", "status"]
+    )
+    .unwrap()
+});
+
+static HTTP_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Time taken to handle HTTP requests, by method, route and status",
+        &["method", "route", "status"]
+    )
+    .unwrap()
+});
+
+static DB_POOL_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "db_pool_connections",
+        "Open database connections, by state (in_use or idle)",
+        &["state"]
+    )
+    .unwrap()
+});
+
+static DB_QUERY_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "db_query_errors_total",
+        "Failed database queries, by query and error code",
+        &["query", "code"]
+    )
+    .unwrap()
+});
+
+pub static COURSES_CREATED: Lazy<IntCounter> =
+    Lazy::new(|| register_int_counter!("courses_created_total", "Courses created").unwrap());
+
+pub static TUTORS_REGISTERED: Lazy<IntCounter> =
+    Lazy::new(|| register_int_counter!("tutors_registered_total", "Tutors registered").unwrap());
+
+pub static STUDENTS_ENROLLED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("students_enrolled_total", "Students enrolled into a course").unwrap()
+});
+
+// Counts a database error, labelled with the dbaccess function it happened
+// in. That is the innermost span, kept alive by `telemetry::init_tracing`
+// whatever the log level; without a subscriber the query is "unknown".
+pub(crate) fn count_query_error(code: ErrorCode) {
+    let span = tracing::Span::current();
+    let query = span.metadata().map_or("unknown", |meta| meta.name());
+    DB_QUERY_ERRORS
+        .with_label_values(&[query, code.as_str()])
+        .inc();
+}
+
+// Middleware (`middleware::from_fn(track_requests)`) counting and timing
+// every request. Routes are labelled with their pattern, e.g.
+// /courses/{tutor_id}, to keep the number of series bounded.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let result = next.call(req).await;
+    let (route, status) = match &result {
+        Ok(res) => (
+            res.request()
+                .match_pattern()
+                .unwrap_or_else(|| "unmatched".into()),
+            res.status(),
+        ),
+        // Errors of inner middleware, the request is gone by now
+        Err(err) => ("unknown".into(), err.as_response_error().status_code()),
+    };
+
+    let labels = [method.as_str(), route.as_str(), status.as_str()];
+    HTTP_REQUESTS.with_label_values(&labels).inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&labels)
+        .observe(started.elapsed().as_secs_f64());
+
+    result
+}
+
+// Renders every metric in the prometheus text format, sampling the pool of
+// the app first. The pool is only looked at, so a scrape never waits for a
+// connection.
+pub fn render(pool: &PgPool) -> HttpResponse {
+    let size = pool.size() as i64;
+    let idle = pool.num_idle() as i64;
+    DB_POOL_CONNECTIONS
+        .with_label_values(&["in_use"])
+        .set(size - idle);
+    DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(idle);
+
+    // Make sure every metric shows up, even before it was first touched
+    Lazy::force(&HTTP_REQUESTS);
+    Lazy::force(&HTTP_REQUEST_DURATION);
+    Lazy::force(&DB_QUERY_ERRORS);
+    Lazy::force(&COURSES_CREATED);
+    Lazy::force(&TUTORS_REGISTERED);
+    Lazy::force(&STUDENTS_ENROLLED);
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, encoder.format_type()))
+            .body(buffer),
+        Err(err) => {
+            tracing::error!(error = %err, "could not encode metrics");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, web, App};
+
+    #[actix_rt::test]
+    async fn requests_are_counted_by_route() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(track_requests))
+                .route("/courses/{id}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for uri in ["/courses/1", "/courses/2", "/nowhere"] {
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        }
+
+        let courses = HTTP_REQUESTS.with_label_values(&["GET", "/courses/{id}", "200"]);
+        let unmatched = HTTP_REQUESTS.with_label_values(&["GET", "unmatched", "404"]);
+        assert!(courses.get() >= 2);
+        assert!(unmatched.get() >= 1);
+    }
+
+    #[actix_rt::test]
+    async fn rendering_does_not_need_the_database() {
+        let pool = PgPool::connect_lazy("postgres://user@127.0.0.1:1/none").unwrap();
+
+        let res = render(&pool);
+        assert_eq!(res.status(), actix_web::http::StatusCode::OK);
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("db_pool_connections{state=\"idle\"} 0"));
+    }
+
+    #[actix_rt::test]
+    async fn query_errors_without_span_are_unknown() {
+        count_query_error(ErrorCode::Conflict);
+        let errors = DB_QUERY_ERRORS.with_label_values(&["unknown", "CONFLICT"]);
+        assert!(errors.get() >= 1);
+    }
+}
@@ -0,0 +1,117 @@
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::postgres::PgPool;
+use std::collections::HashSet;
+use std::io;
+
+// Applies every pending migration before serving. A failure is logged and
+// returned, so that the server exits instead of serving an outdated schema.
+pub async fn run_migrations(pool: &PgPool, migrator: &Migrator) -> io::Result<()> {
+    migrator.run(pool).await.map_err(|err| {
+        tracing::error!(error = %err, "Database migrations failed");
+        io::Error::other(format!("Database migrations failed: {}", err))
+    })
+}
+
+// Handles `<bin_name> migrate ...`: `up` (the default) applies every pending
+// migration, `down <version>` reverts the ones newer than version and
+// `status` lists which migrations are applied.
+pub async fn run_migrate_command(
+    pool: &PgPool,
+    migrator: &Migrator,
+    bin_name: &str,
+    args: &[String],
+) -> io::Result<()> {
+    migrate_command(pool, migrator, bin_name, args)
+        .await
+        .map_err(|err| io::Error::other(err.to_string()))
+}
+
+fn usage(bin_name: &str) -> MigrateError {
+    MigrateError::Source(
+        format!("usage: {} migrate [up | down <version> | status]", bin_name).into(),
+    )
+}
+
+async fn migrate_command(
+    pool: &PgPool,
+    migrator: &Migrator,
+    bin_name: &str,
+    args: &[String],
+) -> Result<(), MigrateError> {
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] | ["up"] => {
+            migrator.run(pool).await?;
+            println!("Database is up to date");
+        }
+        ["down", version] => {
+            let target = version.parse().map_err(|_| usage(bin_name))?;
+            migrator.undo(pool, target).await?;
+            println!("Database reverted to version {}", target);
+        }
+        ["status"] => {
+            let mut conn = pool.acquire().await?;
+            conn.ensure_migrations_table().await?;
+            let applied: HashSet<i64> = conn
+                .list_applied_migrations()
+                .await?
+                .into_iter()
+                .map(|migration| migration.version)
+                .collect();
+
+            for migration in migrator
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+            {
+                let state = if applied.contains(&migration.version) {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!(
+                    "{:>5} {:<8} {}",
+                    migration.version, state, migration.description
+                );
+            }
+        }
+        _ => return Err(usage(bin_name)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+
+    #[actix_rt::test]
+    async fn unknown_commands_print_the_usage() {
+        // The usage is answered before the pool connects
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let migrator = Migrator {
+            migrations: Default::default(),
+            ignore_missing: true,
+        };
+        let err = run_migrate_command(&pool, &migrator, "iter5", &["sideways".into()])
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("usage: iter5 migrate [up | down <version> | status]"));
+    }
+
+    #[actix_rt::test]
+    async fn failed_migrations_are_returned() {
+        let pool = PgPoolOptions::new()
+            .connect_timeout(Duration::from_secs(1))
+            .connect_lazy("postgres://user@127.0.0.1:1/none")
+            .unwrap();
+        let migrator = Migrator {
+            migrations: Default::default(),
+            ignore_missing: true,
+        };
+        let err = run_migrations(&pool, &migrator).await.unwrap_err();
+        assert!(err.to_string().starts_with("Database migrations failed"));
+    }
+}
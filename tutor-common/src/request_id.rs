@@ -0,0 +1,235 @@
+use crate::errors::{ErrorCode, ErrorResponse, EzyTutorError};
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage, HttpResponse, ResponseError};
+use std::fmt;
+use std::time::Instant;
+use tracing::{field, Instrument};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+// Id of the request being handled, to be passed on to other services so that
+// their logs can be matched with ours. None outside of a request.
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+// Correlation id of the current request, available to handlers as
+// `web::ReqData<RequestId>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+// Ids passed in by a client or proxy are reused when they look sane, so that
+// one id can be followed through several services.
+fn incoming_request_id(req: &ServiceRequest) -> Option<String> {
+    let id = req.headers().get(REQUEST_ID_HEADER)?.to_str().ok()?;
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.');
+
+    valid.then(|| id.to_string())
+}
+
+// An error response rendered with the request id, so that errors returned
+// by inner middleware end up with the id as well
+#[derive(Debug)]
+struct TaggedError {
+    status: StatusCode,
+    body: ErrorResponse,
+}
+
+impl fmt::Display for TaggedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.body.code.as_str(), self.body.message)
+    }
+}
+
+impl ResponseError for TaggedError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = self.body.clone().into_http_response(self.status);
+        if let Some(value) = self
+            .body
+            .request_id
+            .as_deref()
+            .and_then(|id| HeaderValue::from_str(id).ok())
+        {
+            response
+                .headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+        response
+    }
+}
+
+fn tag_error(err: &Error, status: StatusCode, id: &str) -> TaggedError {
+    if status.is_server_error() {
+        tracing::error!(error = %err, "request failed");
+    } else {
+        tracing::warn!(error = %err, "request rejected");
+    }
+
+    let body = match err.as_error::<EzyTutorError>() {
+        Some(err) => err.to_response(Some(id)),
+        None => ErrorResponse {
+            code: ErrorCode::for_status(status),
+            message: if status.is_server_error() {
+                "Internal server error".into()
+            } else {
+                err.to_string()
+            },
+            fields: Vec::new(),
+            request_id: Some(id.to_string()),
+        },
+    };
+    TaggedError { status, body }
+}
+
+// Middleware (`middleware::from_fn(request_id)`) tagging every response with
+// an X-Request-Id header and handling the request inside a span recording
+// method, route, status and latency. Error responses get the id in their body
+// too; errors not raised as EzyTutorError, like rejected json payloads, are
+// given the same body shape.
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = incoming_request_id(&req).unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!(
+        "http_request",
+        request_id = %id,
+        method = %req.method(),
+        route = %req.path(),
+        status = field::Empty,
+        latency_ms = field::Empty,
+    );
+    let started = Instant::now();
+
+    let result = CURRENT_REQUEST_ID
+        .scope(id.clone(), next.call(req))
+        .instrument(span.clone())
+        .await;
+    let _entered = span.enter();
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+
+    let res = match result {
+        Ok(res) => res.map_into_boxed_body(),
+        Err(err) => {
+            let status = err.as_response_error().status_code();
+            span.record("status", status.as_u16());
+            return Err(tag_error(&err, status, &id).into());
+        }
+    };
+
+    if let Some(pattern) = res.request().match_pattern() {
+        span.record("route", field::display(pattern));
+    }
+    span.record("status", res.status().as_u16());
+
+    let mut res = match res.response().error() {
+        Some(err) => {
+            let response = tag_error(err, res.status(), &id).error_response();
+            res.into_response(response)
+        }
+        None => {
+            tracing::info!("request completed");
+            res
+        }
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, web, App};
+
+    async fn not_found() -> Result<HttpResponse, EzyTutorError> {
+        Err(EzyTutorError::NotFound("Course id not found".into()))
+    }
+
+    async fn echo_id(id: web::ReqData<RequestId>) -> HttpResponse {
+        assert_eq!(current_request_id().as_ref(), Some(&id.0));
+        HttpResponse::Ok().body(id.0.clone())
+    }
+
+    #[actix_rt::test]
+    async fn errors_carry_the_request_id() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(request_id))
+                .route("/missing", web::get().to(not_found))
+                .route("/echo", web::get().to(echo_id)),
+        )
+        .await;
+        assert_eq!(current_request_id(), None);
+
+        let req = test::TestRequest::get()
+            .uri("/missing")
+            .insert_header((REQUEST_ID_HEADER, "abc-123"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "abc-123");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "code": "NOT_FOUND",
+                "message": "Course id not found",
+                "request_id": "abc-123"
+            })
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header((REQUEST_ID_HEADER, "not valid!"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let header = resp.headers().get(REQUEST_ID_HEADER).unwrap().clone();
+        let body = test::read_body(resp).await;
+        assert_eq!(header.as_bytes(), &body[..]);
+        assert_ne!(&body[..], b"not valid!");
+    }
+
+    #[actix_rt::test]
+    async fn extractor_errors_get_the_same_shape() {
+        let app = test::init_service(App::new().wrap(from_fn(request_id)).route(
+            "/json",
+            web::post().to(|_: web::Json<Vec<i32>>| async { HttpResponse::Ok().finish() }),
+        ))
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/json")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("not json")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVALID_INPUT");
+        assert!(body["request_id"].is_string());
+    }
+}
@@ -0,0 +1,700 @@
+use crate::telemetry::LogFormat;
+use actix_cors::Cors;
+use actix_web::http::header;
+use actix_web::middleware::Condition;
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+
+// Settings are named with dotted keys like `database.max_connections`. Every
+// key can be set in the TOML file named by CONFIG_FILE, as
+// `[database] max_connections = 20`, or with the environment variable named
+// after it, DATABASE_MAX_CONNECTIONS, which wins over the file.
+
+// Defaults of both apps, each app adds its own
+const COMMON_DEFAULTS: &[(&str, &str)] = &[
+    ("server.shutdown_timeout_secs", "30"),
+    ("database.connect_attempts", "10"),
+    ("database.min_connections", "0"),
+    ("database.max_connections", "10"),
+    ("database.acquire_timeout_secs", "30"),
+    ("database.idle_timeout_secs", "600"),
+    ("database.run_migrations", "false"),
+    ("log.format", "pretty"),
+    ("log.filter", "info"),
+    ("cors.max_age_secs", "3600"),
+];
+
+const MAX_CONNECT_DELAY: Duration = Duration::from_secs(30);
+
+// Environment variables in use before settings had a naming scheme. The
+// variable named after the key wins when both are set.
+const ENV_ALIASES: &[(&str, &str)] = &[
+    ("HOST_PORT", "server.bind"),
+    ("RUN_MIGRATIONS", "database.run_migrations"),
+    ("RUST_LOG", "log.filter"),
+    ("EZYTUTOR_API_URL", "backend.url"),
+    ("EZYTUTOR_API_TOKEN", "backend.token"),
+    ("EZYTUTOR_API_TIMEOUT_SECS", "backend.timeout_secs"),
+];
+
+// Every invalid setting found while loading, reported at once
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone)]
+enum Origin {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(name) => write!(f, "environment variable {}", name),
+        }
+    }
+}
+
+fn env_name(key: &str) -> String {
+    key.replace('.', "_").to_uppercase()
+}
+
+// The layered sources of settings. Values are read with the typed getters,
+// which record what is wrong instead of failing right away; `finish` then
+// reports every problem together.
+pub struct Config {
+    defaults: HashMap<String, String>,
+    file: HashMap<String, String>,
+    file_path: PathBuf,
+    env: HashMap<String, String>,
+    read: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl Config {
+    // Loads the file named by CONFIG_FILE, if any, and the environment.
+    // `defaults` are those of the app and override the common ones.
+    pub fn load(defaults: &[(&str, &str)]) -> Result<Self, ConfigError> {
+        let file = match env::var("CONFIG_FILE") {
+            Ok(path) => {
+                let contents = fs::read_to_string(&path)
+                    .map_err(|err| ConfigError(vec![format!("cannot read {}: {}", path, err)]))?;
+                Some((PathBuf::from(path), contents))
+            }
+            Err(_) => None,
+        };
+
+        Config::from_sources(defaults, file, env::vars().collect())
+    }
+
+    pub fn from_sources(
+        defaults: &[(&str, &str)],
+        file: Option<(PathBuf, String)>,
+        env: HashMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let (file_path, file) = match file {
+            Some((path, contents)) => {
+                let table: toml::Value = contents
+                    .parse()
+                    .map_err(|err| ConfigError(vec![format!("{}: {}", path.display(), err)]))?;
+                let mut values = HashMap::new();
+                flatten_toml("", table, &mut values);
+                (path, values)
+            }
+            None => (PathBuf::new(), HashMap::new()),
+        };
+
+        Ok(Config {
+            defaults: COMMON_DEFAULTS
+                .iter()
+                .chain(defaults)
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            file,
+            file_path,
+            env,
+            read: HashSet::new(),
+            errors: Vec::new(),
+        })
+    }
+
+    // The value of `key` from the highest layer setting it. Empty values
+    // count as unset, so that a setting can be cleared from the environment.
+    fn lookup(&mut self, key: &str) -> Option<(String, Origin)> {
+        self.read.insert(key.to_string());
+
+        let env_value = |name: &str| {
+            self.env
+                .get(name)
+                .filter(|value| !value.is_empty())
+                .map(|value| (value.clone(), Origin::Env(name.to_string())))
+        };
+        let alias = ENV_ALIASES
+            .iter()
+            .filter(|(_, aliased)| *aliased == key)
+            .find_map(|(name, _)| env_value(name));
+
+        env_value(&env_name(key))
+            .or(alias)
+            .or_else(|| {
+                self.file
+                    .get(key)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| (value.clone(), Origin::File(self.file_path.clone())))
+            })
+            .or_else(|| {
+                self.defaults
+                    .get(key)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| (value.clone(), Origin::Default))
+            })
+    }
+
+    pub fn optional<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let (value, origin) = self.lookup(key)?;
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.errors.push(format!(
+                    "{} = {:?} (from {}) is invalid: {}",
+                    key, value, origin, err
+                ));
+                None
+            }
+        }
+    }
+
+    // A missing or invalid value is recorded and the default of T returned
+    // in its place, to be discarded by `finish`
+    pub fn required<T>(&mut self, key: &str) -> T
+    where
+        T: FromStr + Default,
+        T::Err: fmt::Display,
+    {
+        if self.lookup(key).is_none() {
+            self.errors.push(format!(
+                "{} is not set, set {} or add it to the config file",
+                key,
+                env_name(key)
+            ));
+            return T::default();
+        }
+        self.optional(key).unwrap_or_default()
+    }
+
+    // A comma separated list, or an array in the config file
+    pub fn list(&mut self, key: &str) -> Vec<String> {
+        self.lookup(key)
+            .map(|(value, _)| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Records a value that parsed but is not acceptable
+    pub fn invalid(&mut self, key: &str, reason: &str) {
+        self.errors.push(format!("{} {}", key, reason));
+    }
+
+    // Returns the settings read, or every problem found while reading them.
+    // Keys in the config file that nothing read are most likely typos.
+    pub fn finish<T>(mut self, settings: T) -> Result<T, ConfigError> {
+        let mut unknown: Vec<&String> = self
+            .file
+            .keys()
+            .filter(|key| !self.read.contains(*key))
+            .collect();
+        unknown.sort();
+        for key in unknown {
+            self.errors.push(format!(
+                "{} in {} is not a known setting",
+                key,
+                self.file_path.display()
+            ));
+        }
+
+        if self.errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(ConfigError(self.errors))
+        }
+    }
+}
+
+fn flatten_toml(prefix: &str, value: toml::Value, values: &mut HashMap<String, String>) {
+    let value = match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_toml(&key, value, values);
+            }
+            return;
+        }
+        toml::Value::String(value) => value,
+        toml::Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                toml::Value::String(item) => item,
+                item => item.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        value => value.to_string(),
+    };
+    values.insert(prefix.to_string(), value);
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerSettings {
+    // host:port to listen on
+    pub bind: String,
+    // Defaults to the number of CPUs
+    pub workers: Option<usize>,
+    // How long in-flight requests may take to finish on shutdown
+    pub shutdown_timeout_secs: u64,
+    pub tls: Option<TlsSettings>,
+}
+
+impl ServerSettings {
+    pub fn read(config: &mut Config) -> Self {
+        let bind: String = config.required("server.bind");
+        let has_port = bind
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+        if !bind.is_empty() && !has_port {
+            config.invalid("server.bind", "must be host:port, e.g. 127.0.0.1:3000");
+        }
+
+        let workers = config.optional("server.workers");
+        if workers == Some(0) {
+            config.invalid("server.workers", "must be at least 1");
+        }
+
+        ServerSettings {
+            bind,
+            workers,
+            shutdown_timeout_secs: config.required("server.shutdown_timeout_secs"),
+            tls: TlsSettings::read(config),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    // PEM encoded certificate chain and private key
+    pub cert_file: PathBuf,
+    pub key_file: PathBuf,
+}
+
+impl TlsSettings {
+    // TLS is enabled by setting both files
+    fn read(config: &mut Config) -> Option<Self> {
+        let cert_file: Option<PathBuf> = config.optional("tls.cert_file");
+        let key_file: Option<PathBuf> = config.optional("tls.key_file");
+
+        for (key, file) in [("tls.cert_file", &cert_file), ("tls.key_file", &key_file)] {
+            if file
+                .as_deref()
+                .is_some_and(|file| !Path::new(file).is_file())
+            {
+                config.invalid(key, "is not a readable file");
+            }
+        }
+
+        match (cert_file, key_file) {
+            (Some(cert_file), Some(key_file)) => Some(TlsSettings {
+                cert_file,
+                key_file,
+            }),
+            (None, None) => None,
+            _ => {
+                config.invalid("tls", "needs both cert_file and key_file");
+                None
+            }
+        }
+    }
+
+    pub fn acceptor(&self) -> io::Result<SslAcceptorBuilder> {
+        let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+        builder.set_private_key_file(&self.key_file, SslFiletype::PEM)?;
+        builder.set_certificate_chain_file(&self.cert_file)?;
+        Ok(builder)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseSettings {
+    pub url: String,
+    // Tries at startup before giving up on the database
+    pub connect_attempts: u32,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    // How long a query waits for a free connection
+    pub acquire_timeout_secs: u64,
+    // Idle connections are closed after this long
+    pub idle_timeout_secs: u64,
+    // Apply pending migrations before serving
+    pub run_migrations: bool,
+}
+
+impl DatabaseSettings {
+    pub fn read(config: &mut Config) -> Self {
+        let url: String = config.required("database.url");
+        if !url.is_empty() && !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+            config.invalid("database.url", "must be a postgres:// url");
+        }
+
+        let settings = DatabaseSettings {
+            url,
+            connect_attempts: config.required("database.connect_attempts"),
+            min_connections: config.required("database.min_connections"),
+            max_connections: config.required("database.max_connections"),
+            acquire_timeout_secs: config.required("database.acquire_timeout_secs"),
+            idle_timeout_secs: config.required("database.idle_timeout_secs"),
+            run_migrations: config.required("database.run_migrations"),
+        };
+        if settings.connect_attempts == 0 {
+            config.invalid("database.connect_attempts", "must be at least 1");
+        }
+        if settings.max_connections == 0 {
+            config.invalid("database.max_connections", "must be at least 1");
+        }
+        if settings.min_connections > settings.max_connections {
+            config.invalid(
+                "database.min_connections",
+                "must not be greater than database.max_connections",
+            );
+        }
+        settings
+    }
+
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .min_connections(self.min_connections)
+            .max_connections(self.max_connections)
+            .connect_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(Duration::from_secs(self.idle_timeout_secs))
+    }
+
+    // Connects the pool, retrying with a growing delay so that a database
+    // that is still starting up does not stop the server
+    pub async fn connect(&self) -> Result<PgPool, sqlx::Error> {
+        let mut delay = Duration::from_millis(500);
+        let mut attempt = 1;
+        loop {
+            match self.pool_options().connect(&self.url).await {
+                Ok(pool) => return Ok(pool),
+                Err(err) if attempt < self.connect_attempts => {
+                    tracing::warn!(
+                        attempt,
+                        error = %err,
+                        "Cannot connect to the database, retrying in {:?}",
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_CONNECT_DELAY);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogSettings {
+    pub format: LogFormat,
+    // Levels in the syntax of RUST_LOG, e.g. `info,sqlx=warn`
+    pub filter: String,
+}
+
+impl LogSettings {
+    pub fn read(config: &mut Config) -> Self {
+        let format = config.optional("log.format").unwrap_or(LogFormat::Pretty);
+        let filter: String = config.required("log.filter");
+        if let Err(err) = EnvFilter::try_new(&filter) {
+            config.invalid("log.filter", &format!("is invalid: {}", err));
+        }
+
+        LogSettings { format, filter }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsSettings {
+    // Origins allowed to call the app from a browser, `*` allows any.
+    // Without any, no CORS headers are sent and browsers refuse
+    // cross-origin calls.
+    pub allowed_origins: Vec<String>,
+    pub max_age_secs: usize,
+}
+
+impl CorsSettings {
+    pub fn read(config: &mut Config) -> Self {
+        let allowed_origins = config.list("cors.allowed_origins");
+        for origin in &allowed_origins {
+            if origin != "*" && !origin.starts_with("http://") && !origin.starts_with("https://") {
+                config.invalid(
+                    "cors.allowed_origins",
+                    &format!("contains {:?}, origins must be * or http(s) urls", origin),
+                );
+            }
+        }
+
+        CorsSettings {
+            allowed_origins,
+            max_age_secs: config.required("cors.max_age_secs"),
+        }
+    }
+
+    pub fn middleware(&self) -> Condition<Cors> {
+        let cors = self
+            .allowed_origins
+            .iter()
+            .fold(Cors::default(), |cors, origin| match origin.as_str() {
+                "*" => cors.allow_any_origin(),
+                origin => cors.allowed_origin(origin),
+            });
+
+        let cors = cors
+            .allowed_methods(["GET", "POST", "PUT", "PATCH", "DELETE"])
+            .allowed_headers([
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                header::HeaderName::from_static(crate::request_id::REQUEST_ID_HEADER),
+            ])
+            .expose_headers([header::HeaderName::from_static(
+                crate::request_id::REQUEST_ID_HEADER,
+            )])
+            .max_age(self.max_age_secs);
+
+        // Cors would reject same-origin requests carrying an Origin header,
+        // like form posts, when no origin is allowed
+        Condition::new(!self.allowed_origins.is_empty(), cors)
+    }
+}
+
+// Argon2 parameters of new password hashes. The defaults follow the OWASP
+// recommendation for argon2id (19 MiB of memory, 2 iterations, 1 lane) and
+// can be raised as hardware gets faster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Settings {
+    pub variant: argon2::Variant,
+    // In KiB
+    pub mem_cost: u32,
+    pub time_cost: u32,
+    pub lanes: u32,
+}
+
+impl Default for Argon2Settings {
+    fn default() -> Self {
+        Argon2Settings {
+            variant: argon2::Variant::Argon2id,
+            mem_cost: 19456,
+            time_cost: 2,
+            lanes: 1,
+        }
+    }
+}
+
+impl Argon2Settings {
+    pub fn read(config: &mut Config) -> Self {
+        let default = Argon2Settings::default();
+
+        let variant = match config.optional::<String>("argon2.variant") {
+            Some(variant) => argon2::Variant::from_str(&variant).unwrap_or_else(|_| {
+                config.invalid("argon2.variant", "must be argon2d, argon2i or argon2id");
+                default.variant
+            }),
+            None => default.variant,
+        };
+        let settings = Argon2Settings {
+            variant,
+            mem_cost: config
+                .optional("argon2.mem_cost")
+                .unwrap_or(default.mem_cost),
+            time_cost: config
+                .optional("argon2.time_cost")
+                .unwrap_or(default.time_cost),
+            lanes: config.optional("argon2.lanes").unwrap_or(default.lanes),
+        };
+
+        // rust-argon2 checks the parameters before the salt, so hashing with
+        // an empty salt fails on the salt, without hashing anything, exactly
+        // when the parameters are usable
+        match argon2::hash_raw(&[], &[], &settings.argon2_config()) {
+            Err(argon2::Error::SaltTooShort) => {}
+            Err(err) => config.invalid("argon2", &format!("parameters are invalid: {}", err)),
+            Ok(_) => unreachable!("an empty salt is too short"),
+        }
+        settings
+    }
+
+    pub fn argon2_config(&self) -> argon2::Config<'static> {
+        argon2::Config {
+            variant: self.variant,
+            version: argon2::Version::Version13,
+            mem_cost: self.mem_cost,
+            time_cost: self.time_cost,
+            lanes: self.lanes,
+            ..argon2::Config::default()
+        }
+    }
+}
+
+// Settings every server has
+#[derive(Debug, Clone)]
+pub struct CommonSettings {
+    pub server: ServerSettings,
+    pub database: DatabaseSettings,
+    pub log: LogSettings,
+    pub cors: CorsSettings,
+}
+
+impl CommonSettings {
+    pub fn read(config: &mut Config) -> Self {
+        CommonSettings {
+            server: ServerSettings::read(config),
+            database: DatabaseSettings::read(config),
+            log: LogSettings::read(config),
+            cors: CorsSettings::read(config),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(file: &str, env: &[(&str, &str)]) -> Config {
+        Config::from_sources(
+            &[("server.bind", "127.0.0.1:3000")],
+            Some((PathBuf::from("test.toml"), file.to_string())),
+            env.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn layers_override_each_other() {
+        let mut config = config(
+            "[database]\nurl = \"postgres://file\"\nmax_connections = 20\n[cors]\nallowed_origins = [\"http://a\", \"http://b\"]",
+            &[("DATABASE_MAX_CONNECTIONS", "30"), ("HOST_PORT", "0.0.0.0:80")],
+        );
+        let settings = CommonSettings::read(&mut config);
+        let settings = config.finish(settings).unwrap();
+
+        assert_eq!(settings.server.bind, "0.0.0.0:80");
+        assert_eq!(settings.database.url, "postgres://file");
+        assert_eq!(settings.database.max_connections, 30);
+        assert_eq!(settings.database.min_connections, 0);
+        assert_eq!(settings.cors.allowed_origins, ["http://a", "http://b"]);
+        assert!(settings.server.tls.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn connecting_gives_up_after_the_last_attempt() {
+        let mut config = config(
+            "",
+            &[
+                ("DATABASE_URL", "postgres://user@127.0.0.1:1/none"),
+                ("DATABASE_ACQUIRE_TIMEOUT_SECS", "1"),
+            ],
+        );
+        let settings = DatabaseSettings::read(&mut config);
+        let mut settings = config.finish(settings).unwrap();
+        settings.connect_attempts = 2;
+
+        assert!(settings.connect().await.is_err());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let mut config = config(
+            "[database]\nmax_conections = 5\n[log]\nformat = \"xml\"",
+            &[
+                ("SERVER_BIND", "localhost"),
+                ("DATABASE_MIN_CONNECTIONS", "many"),
+                ("TLS_CERT_FILE", "/nonexistent/cert.pem"),
+            ],
+        );
+        let settings = CommonSettings::read(&mut config);
+        let err = config.finish(settings).unwrap_err();
+
+        let problems = err.to_string();
+        for expected in [
+            "server.bind must be host:port",
+            "database.url is not set, set DATABASE_URL",
+            "database.min_connections = \"many\" (from environment variable DATABASE_MIN_CONNECTIONS) is invalid",
+            "log.format = \"xml\" (from test.toml) is invalid",
+            "tls.cert_file is not a readable file",
+            "tls needs both cert_file and key_file",
+            "database.max_conections in test.toml is not a known setting",
+        ] {
+            assert!(problems.contains(expected), "{} in {}", expected, problems);
+        }
+        assert_eq!(err.0.len(), 7);
+    }
+
+    #[test]
+    fn argon2_parameters_are_checked() {
+        let mut valid = config("", &[("ARGON2_MEM_COST", "65536")]);
+        let settings = Argon2Settings::read(&mut valid);
+        let settings = valid.finish(settings).unwrap();
+        assert_eq!(settings.mem_cost, 65536);
+        assert_eq!(settings.variant, argon2::Variant::Argon2id);
+
+        let mut invalid = config(
+            "[argon2]\nvariant = \"argon3\"",
+            &[("ARGON2_MEM_COST", "4"), ("ARGON2_TIME_COST", "-1")],
+        );
+        let settings = Argon2Settings::read(&mut invalid);
+        let problems = invalid.finish(settings).unwrap_err().to_string();
+        for expected in [
+            "argon2.variant must be argon2d, argon2i or argon2id",
+            "argon2.time_cost = \"-1\" (from environment variable ARGON2_TIME_COST) is invalid",
+            "argon2 parameters are invalid: Memory cost is too small",
+        ] {
+            assert!(problems.contains(expected), "{} in {}", expected, problems);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use actix_web::dev::ServerHandle;
+
+// Stops the server gracefully on SIGTERM or SIGINT: it stops accepting
+// connections and lets in-flight requests finish, for at most the shutdown
+// timeout of the server. Actix would stop at once on SIGINT, so servers
+// using this are started with `disable_signals`.
+pub async fn stop_on_signal(handle: ServerHandle) {
+    wait_for_signal().await;
+    tracing::info!("Shutting down, draining in-flight requests");
+    handle.stop(true).await;
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("cannot listen for SIGTERM");
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("cannot listen for ctrl-c");
+}
@@ -0,0 +1,67 @@
+use crate::settings::LogSettings;
+use std::str::FromStr;
+use tracing::Subscriber;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+// Output format of the log, the `log.format` setting. Pretty is meant for
+// people reading a terminal, json for log collectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "unknown log format '{}', expected pretty or json",
+                format
+            )),
+        }
+    }
+}
+
+// Installs the global subscriber. Records of crates still using the `log`
+// facade, like actix-web, are forwarded as well.
+pub fn init_tracing(settings: &LogSettings) {
+    let filter = EnvFilter::new(&settings.filter);
+    let output = match settings.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .boxed(),
+    };
+    let query_spans = filter_fn(|meta| meta.is_span() && meta.target().contains("::dbaccess"));
+
+    tracing_subscriber::registry()
+        .with(output.with_filter(filter))
+        .with(QuerySpans.with_filter(query_spans))
+        .init();
+}
+
+// Keeps the spans of dbaccess functions enabled whatever RUST_LOG says, the
+// db_query_errors_total metric is labelled with their names
+struct QuerySpans;
+
+impl<S: Subscriber> Layer<S> for QuerySpans {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_format_is_parsed() {
+        assert_eq!("json".parse(), Ok(LogFormat::Json));
+        assert_eq!("Pretty".parse(), Ok(LogFormat::Pretty));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -1,6 +1,11 @@
 use actix_web::{web, App, HttpServer};
+use std::env;
 use std::io;
 use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+#[path = "../errors.rs"]
+mod errors;
 
 #[path = "../handlers.rs"]
 mod handlers;
@@ -14,15 +19,33 @@ mod state;
 #[path = "../models.rs"]
 mod models;
 
+#[path = "../store.rs"]
+mod store;
+
 use routes::*;
 use state::AppState;
+use store::{CourseStore, FileStore, MemoryStore};
 
 #[actix_rt::main]
 async fn main() -> io::Result<()> {
+    // RUST_LOG picks the levels, info by default
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    // Courses are kept in COURSES_DIR when it is set, and lost on restart
+    // otherwise
+    let courses: Box<dyn CourseStore> = match env::var("COURSES_DIR") {
+        Ok(dir) => Box::new(FileStore::open(dir)?),
+        Err(_) => Box::new(MemoryStore::default()),
+    };
+
     let shared_data = web::Data::new(AppState {
         health_check_response: "I'm good. You've already asked me".to_string(),
         visit_count: Mutex::new(0),
-        courses: Mutex::new(vec![]),
+        courses,
     });
 
     let app = move || {
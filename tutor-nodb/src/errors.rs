@@ -0,0 +1,105 @@
+use actix_web::{error, http::StatusCode, HttpResponse};
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use tutor_api::validation::{field_errors, FieldError};
+use validator::ValidationErrors;
+
+// The error type of tutor-nodb, a lighter take on the EzyTutorError of the
+// tutor web service: the String of each variant is the detail that gets
+// logged, the client gets `message()` and a stable code.
+#[derive(Debug)]
+pub enum EzyTutorError {
+    StoreError(String),
+    NotFound(String),
+    InvalidInput(String),
+    ValidationFailed(Vec<FieldError>),
+}
+
+// Body of every error response, e.g.
+// `{"code":"NOT_FOUND","message":"Course not found"}`
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl EzyTutorError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EzyTutorError::StoreError(_) => "STORE_ERROR",
+            EzyTutorError::NotFound(_) => "NOT_FOUND",
+            EzyTutorError::InvalidInput(_) => "INVALID_INPUT",
+            EzyTutorError::ValidationFailed(_) => "VALIDATION_FAILED",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            EzyTutorError::StoreError(_) => "Could not save course".into(),
+            EzyTutorError::NotFound(msg) | EzyTutorError::InvalidInput(msg) => msg.clone(),
+            EzyTutorError::ValidationFailed(_) => "Validation failed".into(),
+        }
+    }
+}
+
+impl error::ResponseError for EzyTutorError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            EzyTutorError::StoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            EzyTutorError::NotFound(_) => StatusCode::NOT_FOUND,
+            EzyTutorError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            EzyTutorError::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let EzyTutorError::StoreError(_) = self {
+            tracing::error!(error = %self, "request failed");
+        }
+        let fields = match self {
+            EzyTutorError::ValidationFailed(fields) => fields.clone(),
+            _ => Vec::new(),
+        };
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            code: self.code(),
+            message: self.message(),
+            fields,
+        })
+    }
+}
+
+impl std::error::Error for EzyTutorError {}
+
+impl fmt::Display for EzyTutorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EzyTutorError::StoreError(msg)
+            | EzyTutorError::NotFound(msg)
+            | EzyTutorError::InvalidInput(msg) => {
+                write!(f, "{}: {}", self.code(), msg)
+            }
+            EzyTutorError::ValidationFailed(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|err| format!("{} {}", err.field, err.reason))
+                    .collect();
+                write!(f, "{}: {}", self.code(), fields.join(", "))
+            }
+        }
+    }
+}
+
+impl From<io::Error> for EzyTutorError {
+    fn from(err: io::Error) -> Self {
+        EzyTutorError::StoreError(err.to_string())
+    }
+}
+
+impl From<ValidationErrors> for EzyTutorError {
+    fn from(errors: ValidationErrors) -> Self {
+        EzyTutorError::ValidationFailed(field_errors(&errors))
+    }
+}
@@ -1,7 +1,9 @@
+use super::errors::EzyTutorError;
 use super::state::AppState;
-use actix_web::{web, HttpResponse};
-use super::models::Course;
+use actix_web::{http::header, web, HttpResponse};
+use super::models::{course_from, CreateCourse, Page, PageParams, UpdateCourse};
 use chrono::Utc;
+use validator::Validate;
 
 pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpResponse {
     let health_check_response = &app_state.health_check_response;
@@ -13,92 +15,168 @@ pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpRespons
     HttpResponse::Ok().json(&response)
 }
 
-pub async fn new_course(new_course: web::Json<Course>, app_state: web::Data<AppState>) -> HttpResponse {
+pub async fn new_course(new_course: web::Json<CreateCourse>, app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
     println!("Received new course");
+    new_course.validate()?;
 
-    let course_count_for_user = app_state
+    let course = app_state.courses.add(course_from(new_course.into_inner(), Utc::now().naive_utc()))?;
+    let location = format!("/courses/{}/{}", course.tutor_id, course.course_id);
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, location))
+        .json(course))
+}
+
+pub async fn get_courses_for_tutor(app_state: web::Data<AppState>, params: web::Path<(i32,)>, page: web::Query<PageParams>) -> HttpResponse {
+    let tutor_id: i32 = params.0;
+    let (limit, offset) = (page.limit(), page.offset());
+
+    let filtered_courses = app_state.courses.courses_for_tutor(tutor_id);
+    let total = filtered_courses.len();
+    let items = filtered_courses.into_iter().skip(offset).take(limit).collect();
+
+    HttpResponse::Ok().json(Page::new(items, total as i64, limit as i64, offset as i64))
+}
+
+pub async fn get_course_detail(app_state: web::Data<AppState>, params: web::Path<(i32, i32)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+
+    app_state
         .courses
-        .lock()
-        .unwrap()
-        .clone()
-        .into_iter()
-        .filter(|course| course.tutor_id == new_course.tutor_id)
-        .collect::<Vec<Course>>()
-        .len();
-
-    let new_course = Course {
-        tutor_id: new_course.tutor_id,
-        course_id: Some(course_count_for_user + 1),
-        course_name: new_course.course_name.clone(),
-        posted_time: Some(Utc::now().naive_utc())
-    };
-
-    app_state.courses.lock().unwrap().push(new_course);
-    HttpResponse::Ok().json("Added course")
+        .course(tutor_id, course_id)
+        .map(|course| HttpResponse::Ok().json(course))
+        .ok_or_else(|| EzyTutorError::NotFound("Course not found".into()))
 }
 
-pub async fn get_courses_for_tutor(app_state: web::Data<AppState>, params: web::Path<(usize,)>) -> HttpResponse {
-    let tutor_id: usize = params.0;
+pub async fn update_course_details(app_state: web::Data<AppState>, update_course: web::Json<UpdateCourse>, params: web::Path<(i32, i32)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    update_course.validate()?;
 
-    let filtered_courses = app_state
+    app_state
         .courses
-        .lock()
-        .unwrap()
-        .clone()
-        .into_iter()
-        .filter(|course| course.tutor_id == tutor_id)
-        .collect::<Vec<Course>>();
-
-    if filtered_courses.len() > 0 {
-        HttpResponse::Ok().json(filtered_courses)
-    } else {
-        HttpResponse::Ok().json("No courses found for tutor".to_string())
-    }
+        .update(tutor_id, course_id, &update_course)?
+        .map(|course| HttpResponse::Ok().json(course))
+        .ok_or_else(|| EzyTutorError::NotFound("Course not found".into()))
 }
 
-pub async fn get_course_detail(app_state: web::Data<AppState>, params: web::Path<(usize, usize)>) -> HttpResponse {
+// The body the tutor web service answers a delete with, which reports the
+// result of its query
+pub const COURSE_DELETED: &str = "Deleted PgQueryResult { rows_affected: 1 } Record";
+
+pub async fn delete_course(app_state: web::Data<AppState>, params: web::Path<(i32, i32)>) -> Result<HttpResponse, EzyTutorError> {
     let (tutor_id, course_id) = params.into_inner();
 
-    let selected_courses = app_state
+    app_state
         .courses
-        .lock()
-        .unwrap()
-        .clone()
-        .into_iter()
-        .find(|x| x.tutor_id == tutor_id && x.course_id == Some(course_id))
-        .ok_or("Course not found");
-
-    if let Ok(course) = selected_courses {
-        HttpResponse::Ok().json(course)
-    } else {
-        HttpResponse::Ok().json("Course not found".to_string())
-    }
+        .delete(tutor_id, course_id)?
+        .map(|_| HttpResponse::Ok().json(COURSE_DELETED))
+        .ok_or_else(|| EzyTutorError::NotFound("Course not found".into()))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::models::Course;
+    use crate::routes::course_routes;
+    use crate::store::MemoryStore;
+    use actix_web::body::to_bytes;
     use actix_web::http::StatusCode;
+    use actix_web::{test, App, ResponseError};
+    use serde_json::{json, Value};
     use std::sync::Mutex;
 
+    fn create_course(course_name: &str) -> web::Json<CreateCourse> {
+        let course = json!({"tutor_id": 1, "course_name": course_name});
+        web::Json(serde_json::from_value(course).unwrap())
+    }
+
+    fn test_course(course_name: &str) -> Course {
+        course_from(create_course(course_name).into_inner(), Utc::now().naive_utc())
+    }
+
+    async fn body_json(resp: HttpResponse) -> Value {
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
     #[actix_rt::test]
     async fn post_course_test() {
-        let course = web::Json(
-            Course {
-                tutor_id: 1,
-                course_id: None,
-                course_name: "Hello, this is test course".into(),
-                posted_time: None,
-            }
+        let course = web::Json(serde_json::from_value(json!({
+            "tutor_id": 1,
+            "course_name": "Hello, this is test course",
+            "course_format": "video",
+            "course_level": "Beginner",
+            "course_price_cents": 2500,
+        })).unwrap());
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
+        });
+
+        let resp = new_course(course, app_state).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers().get(header::LOCATION).unwrap(), "/courses/1/1");
+
+        let body = body_json(resp).await;
+        assert_eq!(body["tutor_id"], 1);
+        assert_eq!(body["course_id"], 1);
+        assert_eq!(body["course_name"], "Hello, this is test course");
+        assert_eq!(body["course_format"], "video");
+        assert_eq!(body["course_level"], "beginner");
+        assert_eq!(body["course_price_cents"], 2500);
+        assert_eq!(body["course_currency"], "USD");
+        assert!(body["posted_time"].is_string());
+    }
+
+    #[actix_rt::test]
+    async fn post_invalid_course_test() {
+        let course = web::Json(serde_json::from_value(json!({
+            "tutor_id": 1,
+            "course_name": " ",
+            "course_currency": "usd",
+        })).unwrap());
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
+        });
+
+        let resp = new_course(course, app_state.clone()).await.unwrap_err().error_response();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            body_json(resp).await,
+            json!({
+                "code": "VALIDATION_FAILED",
+                "message": "Validation failed",
+                "fields": [
+                    {"field": "course_currency", "reason": "must be a three letter ISO 4217 currency code"},
+                    {"field": "course_name", "reason": "must not be blank"},
+                ]
+            })
         );
+        assert!(app_state.courses.courses_for_tutor(1).is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn post_malformed_course_test() {
         let app_state: web::Data<AppState> = web::Data::new(AppState {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
-            courses: Mutex::new(vec![])
+            courses: Box::new(MemoryStore::default())
         });
+        let app = test::init_service(App::new().app_data(app_state).configure(course_routes)).await;
 
-        let resp = new_course(course, app_state).await;
-        assert_eq!(resp.status(), StatusCode::OK)
+        for req in [
+            test::TestRequest::post().uri("/courses/").set_payload("{\"tutor_id\": ").insert_header((header::CONTENT_TYPE, "application/json")),
+            test::TestRequest::put().uri("/courses/1/1").set_payload("[]").insert_header((header::CONTENT_TYPE, "application/json")),
+        ] {
+            let resp = test::call_service(&app, req.to_request()).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(
+                test::read_body_json::<Value, _>(resp).await,
+                json!({"code": "INVALID_INPUT", "message": "Please provide valid Json input"})
+            );
+        }
     }
 
     #[actix_rt::test]
@@ -106,13 +184,57 @@ mod test {
         let app_state: web::Data<AppState> = web::Data::new(AppState {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
-            courses: Mutex::new(vec![])
+            courses: Box::new(MemoryStore::default())
         });
+        app_state.courses.add(test_course("First course")).unwrap();
+        app_state.courses.add(test_course("Second course")).unwrap();
+
+        let tutor_id: web::Path<(i32,)> = web::Path::from((1,));
+        let page = web::Query::from_query("").unwrap();
+        let resp = get_courses_for_tutor(app_state.clone(), tutor_id, page).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = body_json(resp).await;
+        let names: Vec<&Value> = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|course| &course["course_name"])
+            .collect();
+        assert_eq!(names, [&json!("First course"), &json!("Second course")]);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["next_cursor"], Value::Null);
 
-        let tutor_id: web::Path<(usize,)> = web::Path::from((1,));
-        let resp = get_courses_for_tutor(app_state, tutor_id).await;
+        // Pages are the ones of the tutor web service
+        let tutor_id: web::Path<(i32,)> = web::Path::from((1,));
+        let page = web::Query::from_query("limit=1").unwrap();
+        let page: Page<Course> = serde_json::from_value(body_json(get_courses_for_tutor(app_state.clone(), tutor_id, page).await).await).unwrap();
+        assert_eq!(page.items[0].course_name, "First course");
+        assert_eq!(page.next_cursor, Some(1));
 
-        assert_eq!(resp.status(), StatusCode::OK)
+        let tutor_id: web::Path<(i32,)> = web::Path::from((1,));
+        let page = web::Query::from_query("limit=1&cursor=1").unwrap();
+        let page: Page<Course> = serde_json::from_value(body_json(get_courses_for_tutor(app_state, tutor_id, page).await).await).unwrap();
+        assert_eq!(page.items[0].course_name, "Second course");
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[actix_rt::test]
+    async fn get_all_courses_empty() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
+        });
+
+        let tutor_id: web::Path<(i32,)> = web::Path::from((1,));
+        let page = web::Query::from_query("").unwrap();
+        let resp = get_courses_for_tutor(app_state, tutor_id, page).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            body_json(resp).await,
+            json!({"items": [], "total": 0, "limit": 20, "offset": 0, "next_cursor": null})
+        );
     }
 
     #[actix_rt::test]
@@ -120,12 +242,94 @@ mod test {
         let app_state: web::Data<AppState> = web::Data::new(AppState {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
-            courses: Mutex::new(vec![])
+            courses: Box::new(MemoryStore::default())
+        });
+        app_state.courses.add(test_course("Test course")).unwrap();
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, 1));
+        let resp = get_course_detail(app_state, params).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = body_json(resp).await;
+        assert_eq!(body["course_id"], 1);
+        assert_eq!(body["course_name"], "Test course");
+    }
+
+    #[actix_rt::test]
+    async fn get_one_course_not_found() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
+        });
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, 1));
+        let resp = get_course_detail(app_state, params).await.unwrap_err().error_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            body_json(resp).await,
+            json!({"code": "NOT_FOUND", "message": "Course not found"})
+        );
+    }
+
+    #[actix_rt::test]
+    async fn update_course_success() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
+        });
+        app_state.courses.add(test_course("Test course")).unwrap();
+
+        let update_course = web::Json(UpdateCourse {
+            course_description: Some("All about testing".into()),
+            ..Default::default()
+        });
+        let params: web::Path<(i32, i32)> = web::Path::from((1, 1));
+        let resp = update_course_details(app_state, update_course, params).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = body_json(resp).await;
+        assert_eq!(body["course_name"], "Test course");
+        assert_eq!(body["course_description"], "All about testing");
+    }
+
+    #[actix_rt::test]
+    async fn update_course_not_found() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
+        });
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, 1));
+        let resp = update_course_details(app_state, web::Json(UpdateCourse::default()), params)
+            .await
+            .unwrap_err()
+            .error_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            body_json(resp).await,
+            json!({"code": "NOT_FOUND", "message": "Course not found"})
+        );
+    }
+
+    #[actix_rt::test]
+    async fn delete_course_success() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            courses: Box::new(MemoryStore::default())
         });
+        app_state.courses.add(test_course("Test course")).unwrap();
 
-        let params: web::Path<(usize, usize)> = web::Path::from((1, 1));
-        let resp = get_course_detail(app_state, params).await;
+        let params: web::Path<(i32, i32)> = web::Path::from((1, 1));
+        let resp = delete_course(app_state.clone(), params).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body_json(resp).await, json!(COURSE_DELETED));
 
-        assert_eq!(resp.status(), StatusCode::OK)
+        let params: web::Path<(i32, i32)> = web::Path::from((1, 1));
+        let err = delete_course(app_state, params).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
     }
 }
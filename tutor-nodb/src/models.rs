@@ -1,22 +1,76 @@
-use actix_web::web;
 use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Course {
-    pub tutor_id: usize,
-    pub course_id: Option<usize>,
-    pub course_name: String,
-    pub posted_time: Option<NaiveDateTime>,
+// Courses and pages are those of the tutor web service, so that this service
+// can stand in for it
+pub use tutor_api::course::{Course, CreateCourse, UpdateCourse, DEFAULT_CURRENCY};
+pub use tutor_api::page::Page;
+
+pub const DEFAULT_PAGE_LIMIT: usize = 20;
+pub const MAX_PAGE_LIMIT: usize = 100;
+
+// Paging parameters of the course listing, as taken by the tutor web service.
+// `cursor` is accepted as an alias of `offset`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PageParams {
+    pub limit: Option<usize>,
+    #[serde(alias = "cursor")]
+    pub offset: Option<usize>,
 }
 
-impl From<web::Json<Course>> for Course {
-    fn from(course: web::Json<Course>) -> Self {
-        Self {
-            tutor_id: course.tutor_id,
-            course_id: course.course_id,
-            course_name: course.course_name.clone(),
-            posted_time: course.posted_time,
-        }
+impl PageParams {
+    pub fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset.unwrap_or_default()
+    }
+}
+
+// The course to store for `new_course`. Its course_id is handed out by the
+// store.
+pub fn course_from(new_course: CreateCourse, posted_time: NaiveDateTime) -> Course {
+    Course {
+        course_id: 0,
+        tutor_id: new_course.tutor_id,
+        course_name: new_course.course_name,
+        course_description: new_course.course_description,
+        course_format: new_course.course_format,
+        course_structure: new_course.course_structure,
+        course_duration_minutes: new_course.course_duration_minutes,
+        course_price_cents: new_course.course_price_cents,
+        course_currency: new_course
+            .course_currency
+            .unwrap_or_else(|| DEFAULT_CURRENCY.into()),
+        course_lenguage: new_course.course_lenguage,
+        course_level: new_course.course_level,
+        course_capacity: new_course.course_capacity,
+        posted_time: Some(posted_time),
+    }
+}
+
+// Fields left out of `update` keep their value
+pub fn apply_update(course: &mut Course, update: &UpdateCourse) {
+    let update = update.clone();
+    if let Some(course_name) = update.course_name {
+        course.course_name = course_name;
+    }
+    if let Some(course_currency) = update.course_currency {
+        course.course_currency = course_currency;
     }
+    course.course_description = update
+        .course_description
+        .or(course.course_description.take());
+    course.course_format = update.course_format.or(course.course_format);
+    course.course_structure = update.course_structure.or(course.course_structure.take());
+    course.course_duration_minutes = update
+        .course_duration_minutes
+        .or(course.course_duration_minutes);
+    course.course_price_cents = update.course_price_cents.or(course.course_price_cents);
+    course.course_lenguage = update.course_lenguage.or(course.course_lenguage.take());
+    course.course_level = update.course_level.or(course.course_level);
+    course.course_capacity = update.course_capacity.or(course.course_capacity);
 }
@@ -1,14 +1,26 @@
+use super::errors::EzyTutorError;
 use super::handlers::*;
 use actix_web::web;
 
 pub fn general_routes(cfg: &mut web::ServiceConfig) {
-    cfg.route("/health", web::get().to(health_check_handler));
+    // /health/live is where the web app checks the tutor web service
+    cfg.route("/health", web::get().to(health_check_handler))
+        .route("/health/live", web::get().to(health_check_handler));
 }
 
 pub fn course_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/courses")
+        // Malformed bodies get the JSON error body of every other error
+        .app_data(web::JsonConfig::default().error_handler(|_err, _req| {
+            EzyTutorError::InvalidInput("Please provide valid Json input".into()).into()
+        }))
         .route("/", web::post().to(new_course))
         .route("/{user_id}", web::get().to(get_courses_for_tutor))
         .route("/{user_id}/{course_id}", web::get().to(get_course_detail))
+        // Both update only the fields that are sent, like PUT of the tutor
+        // web service
+        .route("/{user_id}/{course_id}", web::put().to(update_course_details))
+        .route("/{user_id}/{course_id}", web::patch().to(update_course_details))
+        .route("/{user_id}/{course_id}", web::delete().to(delete_course))
     );
 }
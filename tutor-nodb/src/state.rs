@@ -1,9 +1,8 @@
-use super::models::Course;
+use super::store::CourseStore;
 use std::sync::Mutex;
 
-#[derive(Debug)]
 pub struct AppState {
     pub health_check_response: String,
     pub visit_count: Mutex<u32>,
-    pub courses: Mutex<Vec<Course>>,
+    pub courses: Box<dyn CourseStore>,
 }
@@ -0,0 +1,669 @@
+use super::models::{apply_update, Course, UpdateCourse};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock, RwLockWriteGuard};
+
+// Where the handlers keep courses. A store hands out the course ids, so that
+// two courses posted at the same time never get the same one, and applies
+// updates itself, so that concurrent updates are not lost.
+pub trait CourseStore: Send + Sync {
+    // Stores `course` under the next course id of its tutor, whatever its
+    // course_id was, and returns it
+    fn add(&self, course: Course) -> io::Result<Course>;
+
+    // Returns the updated course, or None when there is no such course
+    fn update(
+        &self,
+        tutor_id: i32,
+        course_id: i32,
+        update: &UpdateCourse,
+    ) -> io::Result<Option<Course>>;
+
+    // Returns the deleted course, or None when there is no such course
+    fn delete(&self, tutor_id: i32, course_id: i32) -> io::Result<Option<Course>>;
+
+    fn courses_for_tutor(&self, tutor_id: i32) -> Vec<Course>;
+
+    fn course(&self, tutor_id: i32, course_id: i32) -> Option<Course>;
+}
+
+// A change to the courses, as written to the log of a FileStore, e.g.
+// `{"op":"delete","tutor_id":1,"course_id":2}`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Change {
+    Put { course: Course },
+    Delete { tutor_id: i32, course_id: i32 },
+}
+
+// Courses of every tutor, by course id. Course ids are handed out per tutor
+// and never reused, not even those of deleted courses.
+#[derive(Debug, Default)]
+struct CourseIndex {
+    tutors: HashMap<i32, TutorCourses>,
+}
+
+#[derive(Debug, Default)]
+struct TutorCourses {
+    courses: BTreeMap<i32, Course>,
+    last_course_id: i32,
+}
+
+// What a FileStore keeps in courses.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Snapshot {
+    courses: Vec<Course>,
+    last_course_ids: BTreeMap<i32, i32>,
+}
+
+impl CourseIndex {
+    fn from_snapshot(snapshot: Snapshot) -> Self {
+        let mut index = CourseIndex::default();
+        for (tutor_id, last_course_id) in snapshot.last_course_ids {
+            index.tutors.entry(tutor_id).or_default().last_course_id = last_course_id;
+        }
+        for course in snapshot.courses {
+            index.insert(course);
+        }
+        index
+    }
+
+    fn to_snapshot(&self) -> Snapshot {
+        Snapshot {
+            courses: self
+                .tutors
+                .values()
+                .flat_map(|tutor| tutor.courses.values().cloned())
+                .collect(),
+            last_course_ids: self
+                .tutors
+                .iter()
+                .map(|(tutor_id, tutor)| (*tutor_id, tutor.last_course_id))
+                .collect(),
+        }
+    }
+
+    fn next_course_id(&self, tutor_id: i32) -> i32 {
+        self.tutors
+            .get(&tutor_id)
+            .map_or(0, |tutor| tutor.last_course_id)
+            + 1
+    }
+
+    // Inserts or replaces a course which already has its id
+    fn insert(&mut self, course: Course) {
+        let tutor = self.tutors.entry(course.tutor_id).or_default();
+        tutor.last_course_id = tutor.last_course_id.max(course.course_id);
+        tutor.courses.insert(course.course_id, course);
+    }
+
+    fn apply(&mut self, change: Change) {
+        match change {
+            Change::Put { course } => self.insert(course),
+            Change::Delete {
+                tutor_id,
+                course_id,
+            } => {
+                if let Some(tutor) = self.tutors.get_mut(&tutor_id) {
+                    tutor.courses.remove(&course_id);
+                }
+            }
+        }
+    }
+
+    // Works out the change made by `update`, with the updated course
+    fn update(
+        &self,
+        tutor_id: i32,
+        course_id: i32,
+        update: &UpdateCourse,
+    ) -> Option<(Change, Course)> {
+        let mut course = self.course(tutor_id, course_id)?;
+        apply_update(&mut course, update);
+        Some((
+            Change::Put {
+                course: course.clone(),
+            },
+            course,
+        ))
+    }
+
+    // Works out the change made by a delete, with the deleted course
+    fn delete(&self, tutor_id: i32, course_id: i32) -> Option<(Change, Course)> {
+        let course = self.course(tutor_id, course_id)?;
+        Some((
+            Change::Delete {
+                tutor_id,
+                course_id,
+            },
+            course,
+        ))
+    }
+
+    fn courses_for_tutor(&self, tutor_id: i32) -> Vec<Course> {
+        self.tutors
+            .get(&tutor_id)
+            .map(|tutor| tutor.courses.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn course(&self, tutor_id: i32, course_id: i32) -> Option<Course> {
+        self.tutors
+            .get(&tutor_id)
+            .and_then(|tutor| tutor.courses.get(&course_id))
+            .cloned()
+    }
+}
+
+// Keeps courses in memory only, they are gone on restart
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    index: RwLock<CourseIndex>,
+}
+
+impl MemoryStore {
+    fn commit(
+        mut index: RwLockWriteGuard<CourseIndex>,
+        change: Option<(Change, Course)>,
+    ) -> io::Result<Option<Course>> {
+        Ok(change.map(|(change, course)| {
+            index.apply(change);
+            course
+        }))
+    }
+}
+
+impl CourseStore for MemoryStore {
+    fn add(&self, mut course: Course) -> io::Result<Course> {
+        let mut index = self.index.write().unwrap();
+        course.course_id = index.next_course_id(course.tutor_id);
+        index.insert(course.clone());
+        Ok(course)
+    }
+
+    fn update(
+        &self,
+        tutor_id: i32,
+        course_id: i32,
+        update: &UpdateCourse,
+    ) -> io::Result<Option<Course>> {
+        let index = self.index.write().unwrap();
+        let change = index.update(tutor_id, course_id, update);
+        Self::commit(index, change)
+    }
+
+    fn delete(&self, tutor_id: i32, course_id: i32) -> io::Result<Option<Course>> {
+        let index = self.index.write().unwrap();
+        let change = index.delete(tutor_id, course_id);
+        Self::commit(index, change)
+    }
+
+    fn courses_for_tutor(&self, tutor_id: i32) -> Vec<Course> {
+        self.index.read().unwrap().courses_for_tutor(tutor_id)
+    }
+
+    fn course(&self, tutor_id: i32, course_id: i32) -> Option<Course> {
+        self.index.read().unwrap().course(tutor_id, course_id)
+    }
+}
+
+// The log is folded into the snapshot once it holds this many changes
+const COMPACT_AFTER: usize = 1000;
+
+// Keeps courses in memory and on disk, in a directory holding
+// - courses.json, a snapshot of every course and of the last course id of
+//   each tutor
+// - courses.log, the changes made since, one JSON object per line
+// Every change is appended to the log before it is acknowledged. Writers take
+// turns on the log, readers only need the index.
+#[derive(Debug)]
+pub struct FileStore {
+    snapshot_path: PathBuf,
+    log_path: PathBuf,
+    index: RwLock<CourseIndex>,
+    log: Mutex<CourseLog>,
+}
+
+#[derive(Debug)]
+struct CourseLog {
+    file: Box<dyn LogFile>,
+    // Bytes of the changes logged so far
+    len: u64,
+    logged: usize,
+}
+
+// What the log is written to, a File but in tests making writes fail
+trait LogFile: Write + Seek + Send + fmt::Debug {
+    fn sync_data(&self) -> io::Result<()>;
+    fn set_len(&self, len: u64) -> io::Result<()>;
+}
+
+impl LogFile for File {
+    fn sync_data(&self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+}
+
+impl CourseLog {
+    fn new(file: File) -> Self {
+        CourseLog {
+            file: Box::new(file),
+            len: 0,
+            logged: 0,
+        }
+    }
+
+    // Appends a line to the log. When that fails, e.g. on a full disk, the
+    // part of the line already written is cut off again, since the next
+    // lines would follow it and keep the store from opening.
+    fn append(&mut self, line: &[u8]) -> io::Result<()> {
+        if let Err(err) = self
+            .file
+            .write_all(line)
+            .and_then(|_| self.file.sync_data())
+        {
+            self.file.set_len(self.len)?;
+            self.file.seek(SeekFrom::Start(self.len))?;
+            return Err(err);
+        }
+        self.len += line.len() as u64;
+        self.logged += 1;
+        Ok(())
+    }
+}
+
+impl FileStore {
+    // Loads the courses saved in `dir`, creating it if needed
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let snapshot_path = dir.join("courses.json");
+        let log_path = dir.join("courses.log");
+
+        let snapshot: Snapshot = match File::open(&snapshot_path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Snapshot::default(),
+            Err(err) => return Err(err),
+        };
+        let mut index = CourseIndex::from_snapshot(snapshot);
+        for change in read_log(&log_path)? {
+            index.apply(change);
+        }
+
+        // Start from a fresh snapshot, which also drops a torn last line
+        write_snapshot(&snapshot_path, &index.to_snapshot())?;
+        let file = File::create(&log_path)?;
+
+        Ok(FileStore {
+            snapshot_path,
+            log_path,
+            index: RwLock::new(index),
+            log: Mutex::new(CourseLog::new(file)),
+        })
+    }
+
+    // Logs `change`, then makes it visible to readers. The caller holds the
+    // log, which keeps other writers from working out changes of their own
+    // meanwhile.
+    fn commit(&self, log: &mut CourseLog, change: Change) -> io::Result<()> {
+        let mut line = serde_json::to_vec(&change)?;
+        line.push(b'\n');
+        log.append(&line)?;
+
+        let mut index = self.index.write().unwrap();
+        index.apply(change);
+        if log.logged >= COMPACT_AFTER {
+            write_snapshot(&self.snapshot_path, &index.to_snapshot())?;
+            *log = CourseLog::new(File::create(&self.log_path)?);
+        }
+        Ok(())
+    }
+}
+
+impl CourseStore for FileStore {
+    fn add(&self, mut course: Course) -> io::Result<Course> {
+        let mut log = self.log.lock().unwrap();
+        course.course_id = self.index.read().unwrap().next_course_id(course.tutor_id);
+        self.commit(
+            &mut log,
+            Change::Put {
+                course: course.clone(),
+            },
+        )?;
+        Ok(course)
+    }
+
+    fn update(
+        &self,
+        tutor_id: i32,
+        course_id: i32,
+        update: &UpdateCourse,
+    ) -> io::Result<Option<Course>> {
+        let mut log = self.log.lock().unwrap();
+        let change = self
+            .index
+            .read()
+            .unwrap()
+            .update(tutor_id, course_id, update);
+        match change {
+            Some((change, course)) => self.commit(&mut log, change).map(|_| Some(course)),
+            None => Ok(None),
+        }
+    }
+
+    fn delete(&self, tutor_id: i32, course_id: i32) -> io::Result<Option<Course>> {
+        let mut log = self.log.lock().unwrap();
+        let change = self.index.read().unwrap().delete(tutor_id, course_id);
+        match change {
+            Some((change, course)) => self.commit(&mut log, change).map(|_| Some(course)),
+            None => Ok(None),
+        }
+    }
+
+    fn courses_for_tutor(&self, tutor_id: i32) -> Vec<Course> {
+        self.index.read().unwrap().courses_for_tutor(tutor_id)
+    }
+
+    fn course(&self, tutor_id: i32, course_id: i32) -> Option<Course> {
+        self.index.read().unwrap().course(tutor_id, course_id)
+    }
+}
+
+// Reads the changes of the log. A last line that does not parse was cut short
+// by a crash while it was written, and was never acknowledged.
+fn read_log(path: &Path) -> io::Result<Vec<Change>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let lines = BufReader::new(file)
+        .lines()
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut changes = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(change) => changes.push(change),
+            Err(_) if index + 1 == lines.len() => {
+                tracing::warn!("Ignoring incomplete last line of {}", path.display())
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(changes)
+}
+
+// Replaces the snapshot at once, so that a crash leaves either the old or
+// the new one
+fn write_snapshot(path: &Path, snapshot: &Snapshot) -> io::Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    let mut tmp = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)?;
+    serde_json::to_writer(&mut tmp, snapshot)?;
+    tmp.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    fn course(tutor_id: i32, course_name: &str) -> Course {
+        Course {
+            course_id: 0,
+            tutor_id,
+            course_name: course_name.into(),
+            course_description: None,
+            course_format: None,
+            course_structure: None,
+            course_duration_minutes: None,
+            course_price_cents: None,
+            course_currency: "USD".into(),
+            course_lenguage: None,
+            course_level: None,
+            course_capacity: None,
+            posted_time: None,
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tutor-nodb-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn course_ids_are_per_tutor() {
+        let store = MemoryStore::default();
+        store.add(course(1, "First")).unwrap();
+        store.add(course(2, "Other tutor")).unwrap();
+        let second = store.add(course(1, "Second")).unwrap();
+
+        assert_eq!(second.course_id, 2);
+        assert_eq!(store.courses_for_tutor(1).len(), 2);
+        assert_eq!(store.course(2, 1).unwrap().course_name, "Other tutor");
+        assert!(store.course(2, 2).is_none());
+    }
+
+    #[test]
+    fn courses_are_updated_and_deleted() {
+        let store = MemoryStore::default();
+        store.add(course(1, "First")).unwrap();
+        let update = UpdateCourse {
+            course_price_cents: Some(1500),
+            ..Default::default()
+        };
+
+        let updated = store.update(1, 1, &update).unwrap().unwrap();
+        assert_eq!(updated.course_name, "First");
+        assert_eq!(updated.course_price_cents, Some(1500));
+        assert_eq!(store.course(1, 1).unwrap().course_price_cents, Some(1500));
+        assert!(store.update(1, 2, &update).unwrap().is_none());
+
+        assert_eq!(store.delete(1, 1).unwrap().unwrap().course_name, "First");
+        assert!(store.course(1, 1).is_none());
+        assert!(store.delete(1, 1).unwrap().is_none());
+        assert_eq!(store.add(course(1, "Second")).unwrap().course_id, 2);
+    }
+
+    // Adds courses for one tutor from several threads at once and checks
+    // that every course got an id of its own
+    fn add_concurrently(store: Arc<dyn CourseStore>, threads: usize, per_thread: usize) {
+        let writers: Vec<_> = (0..threads)
+            .map(|thread| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    (0..per_thread)
+                        .map(|i| {
+                            let name = format!("Course {} of thread {}", i, thread);
+                            store.add(course(1, &name)).unwrap().course_id
+                        })
+                        .collect::<Vec<i32>>()
+                })
+            })
+            .collect();
+
+        let mut ids: Vec<i32> = writers
+            .into_iter()
+            .flat_map(|writer| writer.join().unwrap())
+            .collect();
+        ids.sort_unstable();
+        let expected: Vec<i32> = (1..=(threads * per_thread) as i32).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(store.courses_for_tutor(1).len(), threads * per_thread);
+    }
+
+    #[test]
+    fn concurrent_adds_get_unique_ids() {
+        add_concurrently(Arc::new(MemoryStore::default()), 16, 500);
+    }
+
+    #[test]
+    fn concurrent_adds_to_file_get_unique_ids() {
+        let dir = test_dir("concurrent");
+        add_concurrently(Arc::new(FileStore::open(&dir).unwrap()), 8, 25);
+
+        let store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.courses_for_tutor(1).len(), 200);
+        assert_eq!(
+            store.add(course(1, "After restart")).unwrap().course_id,
+            201
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_store_survives_restart() {
+        let dir = test_dir("restart");
+        {
+            let store = FileStore::open(&dir).unwrap();
+            store.add(course(1, "First")).unwrap();
+        }
+        {
+            let store = FileStore::open(&dir).unwrap();
+            store.add(course(1, "Second")).unwrap();
+        }
+
+        let store = FileStore::open(&dir).unwrap();
+        let names: Vec<String> = store
+            .courses_for_tutor(1)
+            .into_iter()
+            .map(|course| course.course_name)
+            .collect();
+        assert_eq!(names, ["First", "Second"]);
+        assert_eq!(store.course(1, 2).unwrap().course_name, "Second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_store_keeps_updates_and_deletes() {
+        let dir = test_dir("changes");
+        {
+            let store = FileStore::open(&dir).unwrap();
+            store.add(course(1, "Kept")).unwrap();
+            store.add(course(1, "Deleted")).unwrap();
+            let update = UpdateCourse {
+                course_name: Some("Renamed".into()),
+                ..Default::default()
+            };
+            store.update(1, 1, &update).unwrap();
+            store.delete(1, 2).unwrap();
+        }
+        {
+            let store = FileStore::open(&dir).unwrap();
+            assert_eq!(store.course(1, 1).unwrap().course_name, "Renamed");
+            assert!(store.course(1, 2).is_none());
+        }
+
+        // The id of the deleted course is not handed out again, even once the
+        // log was folded into the snapshot
+        let store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.add(course(1, "New")).unwrap().course_id, 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Takes at most `room` more bytes, like a disk running full
+    #[derive(Debug)]
+    struct FullDisk {
+        file: File,
+        room: usize,
+    }
+
+    impl FullDisk {
+        fn log_of(dir: &Path, room: usize) -> Box<dyn LogFile> {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .open(dir.join("courses.log"))
+                .unwrap();
+            file.seek(SeekFrom::End(0)).unwrap();
+            Box::new(FullDisk { file, room })
+        }
+    }
+
+    impl Write for FullDisk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::Error::from_raw_os_error(28));
+            }
+            let written = self.file.write(&buf[..buf.len().min(self.room)])?;
+            self.room -= written;
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.file.flush()
+        }
+    }
+
+    impl Seek for FullDisk {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.file.seek(pos)
+        }
+    }
+
+    impl LogFile for FullDisk {
+        fn sync_data(&self) -> io::Result<()> {
+            self.file.sync_data()
+        }
+
+        fn set_len(&self, len: u64) -> io::Result<()> {
+            self.file.set_len(len)
+        }
+    }
+
+    #[test]
+    fn failed_log_writes_are_cut_off() {
+        let dir = test_dir("full");
+        {
+            let store = FileStore::open(&dir).unwrap();
+            store.add(course(1, "Saved")).unwrap();
+
+            store.log.lock().unwrap().file = FullDisk::log_of(&dir, 10);
+            assert!(store.add(course(1, "Lost")).is_err());
+
+            store.log.lock().unwrap().file = FullDisk::log_of(&dir, usize::MAX);
+            store.add(course(1, "Second")).unwrap();
+            store.add(course(1, "Third")).unwrap();
+        }
+
+        let store = FileStore::open(&dir).unwrap();
+        let names: Vec<String> = store
+            .courses_for_tutor(1)
+            .into_iter()
+            .map(|course| course.course_name)
+            .collect();
+        assert_eq!(names, ["Saved", "Second", "Third"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn torn_last_log_line_is_ignored() {
+        let dir = test_dir("torn");
+        {
+            let store = FileStore::open(&dir).unwrap();
+            store.add(course(1, "Saved")).unwrap();
+        }
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.join("courses.log"))
+            .unwrap();
+        log.write_all(b"{\"op\":\"put\",\"cour").unwrap();
+
+        let store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.courses_for_tutor(1).len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
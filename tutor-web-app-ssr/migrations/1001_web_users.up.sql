@@ -0,0 +1,14 @@
+-- The web app shares its database with the tutor web service, so its
+-- migrations are numbered from 1001 to stay clear of the service ones.
+CREATE TABLE IF NOT EXISTS ezyweb_user (
+    username VARCHAR(20) PRIMARY KEY,
+    tutor_id INT NOT NULL,
+    user_password VARCHAR(255) NOT NULL,
+    user_email VARCHAR(200) UNIQUE
+);
+
+-- databases created with the old dbscripts stored hashes in a CHAR(100)
+-- column and had no email
+ALTER TABLE ezyweb_user ALTER COLUMN user_password TYPE VARCHAR(255);
+UPDATE ezyweb_user SET user_password = trim(user_password);
+ALTER TABLE ezyweb_user ADD COLUMN IF NOT EXISTS user_email VARCHAR(200) UNIQUE;
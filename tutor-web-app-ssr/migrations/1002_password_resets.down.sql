@@ -0,0 +1 @@
+DROP TABLE IF EXISTS ezyweb_password_reset;
@@ -0,0 +1,8 @@
+-- Only the sha256 of a reset token is stored, the token itself is mailed to
+-- the user. A token is single use: used_time is set when it is redeemed.
+CREATE TABLE IF NOT EXISTS ezyweb_password_reset (
+    token_hash CHAR(64) PRIMARY KEY,
+    username VARCHAR(20) NOT NULL REFERENCES ezyweb_user (username) ON DELETE CASCADE,
+    expires_time TIMESTAMP NOT NULL,
+    used_time TIMESTAMP
+);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS ezyweb_login_attempt;
@@ -0,0 +1,11 @@
+-- Failed signins, kept so throttling and lockouts survive restarts. Rows of a
+-- username are removed again once it signs in successfully.
+CREATE TABLE IF NOT EXISTS ezyweb_login_attempt (
+    attempt_id SERIAL PRIMARY KEY,
+    username VARCHAR(20) NOT NULL,
+    client_ip VARCHAR(45) NOT NULL,
+    attempt_time TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ezyweb_login_attempt_username ON ezyweb_login_attempt (username, attempt_time);
+CREATE INDEX IF NOT EXISTS idx_ezyweb_login_attempt_ip ON ezyweb_login_attempt (client_ip, attempt_time);
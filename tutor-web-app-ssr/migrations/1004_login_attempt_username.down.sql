@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_ezyweb_login_attempt_time;
+DELETE FROM ezyweb_login_attempt WHERE length(username) > 20;
+ALTER TABLE ezyweb_login_attempt ALTER COLUMN username TYPE VARCHAR(20);
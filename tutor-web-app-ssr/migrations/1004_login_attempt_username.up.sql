@@ -0,0 +1,6 @@
+-- Signin forms accept usernames of any length and every attempt is recorded,
+-- including those for usernames too long to exist. Old attempts are purged
+-- by attempt_time.
+ALTER TABLE ezyweb_login_attempt ALTER COLUMN username TYPE TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_ezyweb_login_attempt_time ON ezyweb_login_attempt (attempt_time);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS ezyweb_user_email_lower_key;
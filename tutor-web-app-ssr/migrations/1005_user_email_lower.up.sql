@@ -0,0 +1,4 @@
+-- Emails are looked up case-insensitively, so they must also be unique
+-- regardless of case. The UNIQUE constraint of user_email stays for the
+-- exact spelling.
+CREATE UNIQUE INDEX IF NOT EXISTS ezyweb_user_email_lower_key ON ezyweb_user (lower(user_email));
@@ -1,35 +1,27 @@
+// Only the tutor listing of the iter6 client is needed here
+#[allow(dead_code)]
+#[path = "../iter6/client.rs"]
+mod client;
+
 use actix_files as fs;
-use actix_web::{error, web, App, Error, HttpResponse, HttpServer, Result};
-use awc::Client;
-use serde::{Deserialize, Serialize};
+use actix_web::{web, App, HttpResponse, HttpServer, Result};
+use client::EzyTutorApiClient;
+use std::env;
+use std::time::Duration;
 use tera::Tera;
+use tutor_common::errors::{self, EzyTutorError};
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Tutor {
-    pub tutor_id: i32,
-    pub tutor_name: String,
-    pub tutor_pic_url: String,
-    pub tutor_profile: String,
-}
+async fn handle_get_tutors(
+    tmpl: web::Data<Tera>,
+    api_client: web::Data<EzyTutorApiClient>,
+) -> Result<HttpResponse, EzyTutorError> {
+    let tutors = api_client.list_tutors().await?;
 
-async fn handle_get_tutors(tmpl: web::Data<Tera>) -> Result<HttpResponse, Error> {
-    let client = Client::default();
-    let response = client
-        .get("http://localhost:3000/tutors/")
-        .send()
-        .await
-        .unwrap()
-        .body()
-        .await
-        .unwrap();
-
-    let str_list = std::str::from_utf8(&response.as_ref()).unwrap();
-    let tutor_list: Vec<Tutor> = serde_json::from_str(str_list).unwrap();
     let mut ctx = tera::Context::new();
-    ctx.insert("tutors", &tutor_list);
+    ctx.insert("tutors", &tutors);
     let rendered_html = tmpl
         .render("list.html", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("Template error"))?;
+        .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
 
     Ok(HttpResponse::Ok()
         .content_type("text/html")
@@ -40,11 +32,20 @@ async fn handle_get_tutors(tmpl: web::Data<Tera>) -> Result<HttpResponse, Error>
 async fn main() -> std::io::Result<()> {
     println!("Listening on: 127.0.0.1:8080");
 
-    HttpServer::new(|| {
+    let api_url =
+        env::var("EZYTUTOR_API_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let api_client = web::Data::new(EzyTutorApiClient::new(
+        &api_url,
+        None,
+        Duration::from_secs(10),
+    ));
+
+    HttpServer::new(move || {
         let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter4/**/*")).unwrap();
 
         App::new()
             .app_data(web::Data::new(tera))
+            .app_data(api_client.clone())
             .service(fs::Files::new("/static", "/static").show_files_listing())
             .service(web::resource("/tutors").route(web::get().to(handle_get_tutors)))
     })
@@ -1,25 +1,44 @@
 #[path = "../iter5/mod.rs"]
 mod iter5;
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware, web, App, HttpServer};
 use dotenv::dotenv;
 use iter5::{dbaccess, errors, handler, model, routes, state::AppState};
 use routes::app_config;
-use sqlx::postgres::PgPool;
-use std::env;
-
+use std::io;
+use std::process;
 use tera::Tera;
+use tutor_common::request_id::request_id;
+use tutor_common::settings::{CommonSettings, Config};
+use tutor_common::telemetry::init_tracing;
 
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
+async fn main() -> io::Result<()> {
     dotenv().ok();
+    let (settings, api_token) = Config::load(&[("server.bind", "127.0.0.1:8080")])
+        .and_then(|mut config| {
+            let settings = CommonSettings::read(&mut config);
+            let api_token: String = config.required("backend.token");
+            config.finish((settings, api_token))
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+    init_tracing(&settings.log);
 
-    let host_port = env::var("HOST_PORT").expect("HOST:PORT address is not set in .env file");
-    println!("Listening on {}", &host_port);
+    let host_port = settings.server.bind;
+    tracing::info!("Listening on {}", &host_port);
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
-    let db_pool = PgPool::connect(&database_url).await.unwrap();
+    let db_pool = settings
+        .database
+        .connect()
+        .await
+        .map_err(|err| io::Error::other(format!("Cannot connect to the database: {}", err)))?;
 
-    let shared_data = web::Data::new(AppState { db: db_pool });
+    let shared_data = web::Data::new(AppState {
+        db: db_pool,
+        api_token,
+    });
 
     HttpServer::new(move || {
         let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter5/**/*")).unwrap();
@@ -27,6 +46,7 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(tera))
             .app_data(shared_data.clone())
+            .wrap(middleware::from_fn(request_id))
             .configure(app_config)
     })
     .bind(&host_port)?
@@ -1,35 +1,116 @@
 #[path = "../iter6/mod.rs"]
 mod iter6;
-use actix_web::{web, App, HttpServer};
+use actix_web::{cookie::Key, middleware, web, App, HttpServer};
 use dotenv::dotenv;
-use iter6::{dbaccess, errors, model, routes, state::AppState};
-use routes::{app_config, course_config};
-use sqlx::postgres::PgPool;
+use iter6::client::EzyTutorApiClient;
+use iter6::mail::LogMailSender;
+use iter6::session::session_middleware;
+use iter6::{dbaccess, errors, migrate, model, routes, settings, state::AppState};
+use routes::{account_config, app_config, course_config};
 use std::env;
+use std::io;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
 use tera::Tera;
+use tutor_common::metrics::track_requests;
+use tutor_common::migrate::{run_migrate_command, run_migrations};
+use tutor_common::request_id::request_id;
+use tutor_common::shutdown::stop_on_signal;
+use tutor_common::telemetry::init_tracing;
 
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
+async fn main() -> io::Result<()> {
     dotenv().ok();
+    let settings = settings::load().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    init_tracing(&settings.common.log);
 
-    let host_port = env::var("HOST_PORT").expect("HOST:PORT address is not set in .env file");
-    println!("Listening on {}", &host_port);
+    let database = &settings.common.database;
+    let db_pool = database
+        .connect()
+        .await
+        .map_err(|err| io::Error::other(format!("Cannot connect to the database: {}", err)))?;
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
-    let db_pool = PgPool::connect(&database_url).await.unwrap();
+    // `iter6-ssr migrate ...` manages the schema and exits, RUN_MIGRATIONS=true
+    // applies pending migrations before serving
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return run_migrate_command(&db_pool, &migrate::migrator(), "iter6-ssr", &args[1..]).await;
+    }
+    if database.run_migrations {
+        run_migrations(&db_pool, &migrate::migrator()).await?;
+    }
 
-    let shared_data = web::Data::new(AppState { db: db_pool });
+    let api_client = EzyTutorApiClient::new(
+        &settings.backend.url,
+        Some(settings.backend.token.clone()),
+        Duration::from_secs(settings.backend.timeout_secs),
+    );
 
-    HttpServer::new(move || {
-        let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter6/**/*")).unwrap();
+    let session_key = match &settings.session_key {
+        Some(key) => Key::from(key.as_bytes()),
+        None => {
+            tracing::warn!("SESSION_KEY is not set, using a random session key");
+            Key::generate()
+        }
+    };
+    let session_settings = settings.session;
 
+    let shared_data = web::Data::new(AppState {
+        db: db_pool.clone(),
+        api_client,
+        password_config: settings.argon2,
+        login_throttle: settings.login_throttle,
+        mailer: Arc::new(LogMailSender::new(settings.mail_outbox.clone())),
+        public_url: settings.public_url.clone(),
+    });
+
+    // Parsed once up front, so that broken templates stop the server before
+    // it starts listening
+    let templates = settings.templates_dir.join("**").join("*");
+    let tera = Tera::new(&templates.to_string_lossy()).unwrap_or_else(|err| {
+        eprintln!(
+            "cannot load templates from {}: {}",
+            templates.display(),
+            err
+        );
+        process::exit(1);
+    });
+
+    let cors = settings.common.cors.clone();
+    let mut server = HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(tera))
+            .app_data(web::Data::new(tera.clone()))
             .app_data(shared_data.clone())
+            .app_data(web::Data::new(session_settings))
+            .wrap(session_middleware(session_key.clone(), session_settings))
+            .wrap(cors.middleware())
+            .wrap(middleware::from_fn(track_requests))
+            .wrap(middleware::from_fn(request_id))
             .configure(course_config)
+            .configure(account_config)
             .configure(app_config)
     })
-    .bind(&host_port)?
-    .run()
-    .await
+    .shutdown_timeout(settings.common.server.shutdown_timeout_secs)
+    .disable_signals();
+    if let Some(workers) = settings.common.server.workers {
+        server = server.workers(workers);
+    }
+    let bind = &settings.common.server.bind;
+    let server = match &settings.common.server.tls {
+        Some(tls) => server.bind_openssl(bind, tls.acceptor()?)?,
+        None => server.bind(bind)?,
+    };
+    tracing::info!("Listening on {}", bind);
+
+    let server = server.run();
+    actix_web::rt::spawn(stop_on_signal(server.handle()));
+    server.await?;
+
+    db_pool.close().await;
+    tracing::info!("Shut down");
+    Ok(())
 }
@@ -1,5 +1,5 @@
 use actix_files as fs;
-use actix_web::{error, web, App, Error, HttpResponse, HttpServer, Result};
+use actix_web::{App, HttpServer};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
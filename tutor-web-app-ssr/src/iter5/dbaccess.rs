@@ -1,11 +1,13 @@
 use crate::errors::EzyTutorError;
 use crate::model::*;
 use sqlx::postgres::PgPool;
+use tracing::instrument;
 
+#[instrument(skip(pool))]
 pub async fn get_user_record(pool: &PgPool, username: String) -> Result<User, EzyTutorError> {
     let user_row = sqlx::query_as!(
         User,
-        "SELECT * FROM ezyweb_user WHERE username = $1",
+        "SELECT username, tutor_id, user_password FROM ezyweb_user WHERE username = $1",
         username
     )
     .fetch_optional(pool)
@@ -18,6 +20,7 @@ pub async fn get_user_record(pool: &PgPool, username: String) -> Result<User, Ez
     }
 }
 
+#[instrument(skip_all, fields(username = %new_user.username))]
 pub async fn post_new_user(pool: &PgPool, new_user: User) -> Result<User, EzyTutorError> {
     let user_row = sqlx::query_as!(
         User,
@@ -1,10 +1,11 @@
 use crate::dbaccess::{get_user_record, post_new_user};
 use crate::errors::EzyTutorError;
 use crate::iter5::state::AppState;
-use crate::model::{TutorRegisterForm, TutorResponse, User};
+use crate::model::{TutorRegisterForm, User};
 use actix_web::{web, Error, HttpResponse, Result};
 use argon2::{self, Config};
 use serde_json::json;
+use tutor_api::tutor::Tutor;
 
 pub async fn show_register_form(tmpl: web::Data<tera::Tera>) -> Result<HttpResponse, Error> {
     let mut ctx = tera::Context::new();
@@ -58,20 +59,21 @@ pub async fn handle_register(
             let awc_client = awc::Client::default();
             let res = awc_client
                 .post("http://localhost:3000/tutors/")
+                .bearer_auth(&app_state.api_token)
                 .send_json(&new_tutor)
                 .await
                 .unwrap()
                 .body()
                 .await?;
 
-            let tutor_response: TutorResponse = serde_json::from_str(&std::str::from_utf8(&res)?)?;
+            let tutor_response: Tutor = serde_json::from_str(std::str::from_utf8(&res)?)?;
             s = format!("Congratulations. You have been successfully registered with Ezytutor and your tutor id is {}. To start using Ezytutor, please login with your credentials.", tutor_response.tutor_id);
 
-            let salt = b"somerandomsalt";
+            let salt: [u8; 16] = rand::random();
             let config = Config::default();
 
             let hash =
-                argon2::hash_encoded(params.password.clone().as_bytes(), salt, &config).unwrap();
+                argon2::hash_encoded(params.password.clone().as_bytes(), &salt, &config).unwrap();
 
             let user = User {
                 username,
@@ -1,5 +1,5 @@
 pub mod dbaccess;
-pub mod errors;
+pub use tutor_common::errors;
 pub mod handler;
 pub mod model;
 pub mod routes;
@@ -10,14 +10,6 @@ pub struct TutorRegisterForm {
     pub profile: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct TutorResponse {
-    pub tutor_id: i32,
-    pub tutor_name: String,
-    pub tutor_pic_url: String,
-    pub tutor_profile: String,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct User {
     pub username: String,
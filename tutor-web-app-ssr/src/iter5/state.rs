@@ -2,4 +2,6 @@ use sqlx::postgres::PgPool;
 
 pub struct AppState {
     pub db: PgPool,
+    // Sent as bearer token on every call to the tutor web service
+    pub api_token: String,
 }
@@ -0,0 +1,385 @@
+use crate::errors::EzyTutorError;
+use actix_web::http::StatusCode;
+use awc::{ClientRequest, Connector};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{field, Instrument};
+use tutor_api::course::{Course, NewCourse, UpdateCourse};
+use tutor_api::page::Page;
+use tutor_api::tutor::{NewTutor, Tutor};
+use tutor_common::request_id::{current_request_id, REQUEST_ID_HEADER};
+
+// The most tutors the tutor web service hands out per page
+const TUTOR_PAGE_LIMIT: i64 = 100;
+
+// Typed client for the tutor web service. awc clients are bound to the
+// thread that created them, so only the settings are kept here and a client
+// is built for every call.
+#[derive(Debug, Clone)]
+pub struct EzyTutorApiClient {
+    base_url: String,
+    // Sent as bearer token when set, needed for every mutating call
+    api_token: Option<String>,
+    timeout: Duration,
+}
+
+#[derive(Deserialize, Debug)]
+struct ErrorBody {
+    message: String,
+    // Set when the request body was rejected by validation
+    #[serde(default)]
+    fields: Vec<FieldErrorBody>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FieldErrorBody {
+    field: String,
+    reason: String,
+}
+
+impl ErrorBody {
+    fn message(self) -> String {
+        if self.fields.is_empty() {
+            return self.message;
+        }
+
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|err| format!("{} {}", err.field, err.reason))
+            .collect();
+        format!("{}: {}", self.message, fields.join(", "))
+    }
+}
+
+impl EzyTutorApiClient {
+    pub fn new(base_url: &str, api_token: Option<String>, timeout: Duration) -> Self {
+        EzyTutorApiClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_token,
+            timeout,
+        }
+    }
+
+    fn client(&self) -> awc::Client {
+        let builder = awc::Client::builder()
+            .connector(Connector::new().timeout(self.timeout))
+            .timeout(self.timeout);
+
+        match &self.api_token {
+            Some(token) => builder.bearer_auth(token).finish(),
+            None => builder.finish(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub async fn create_tutor(&self, new_tutor: &NewTutor) -> Result<Tutor, EzyTutorError> {
+        let request = self.client().post(self.url("/tutors/"));
+        send(request, Some(new_tutor)).await
+    }
+
+    pub async fn delete_tutor(&self, tutor_id: i32) -> Result<(), EzyTutorError> {
+        let request = self
+            .client()
+            .delete(self.url(&format!("/tutors/{}", tutor_id)));
+        let _: serde_json::Value = send(request, None::<&()>).await?;
+        Ok(())
+    }
+
+    // Every tutor, fetched a page at a time by following next_cursor. Used
+    // by the iter4 tutor listing.
+    #[allow(dead_code)]
+    pub async fn list_tutors(&self) -> Result<Vec<Tutor>, EzyTutorError> {
+        let mut tutors = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let request = self.client().get(self.url(&format!(
+                "/tutors/?limit={}&cursor={}",
+                TUTOR_PAGE_LIMIT, cursor
+            )));
+            let page: Page<Tutor> = send(request, None::<&()>).await?;
+            tutors.extend(page.items);
+
+            match page.next_cursor {
+                None => return Ok(tutors),
+                Some(next_cursor) if next_cursor > cursor => cursor = next_cursor,
+                Some(next_cursor) => {
+                    return Err(EzyTutorError::BackendError(format!(
+                        "/tutors/ answered cursor {} after cursor {}",
+                        next_cursor, cursor
+                    )))
+                }
+            }
+        }
+    }
+
+    pub async fn create_course(
+        &self,
+        tutor_id: i32,
+        new_course: &NewCourse,
+    ) -> Result<Course, EzyTutorError> {
+        let course = new_course.clone().for_tutor(tutor_id);
+
+        let request = self.client().post(self.url("/courses/"));
+        send(request, Some(&course)).await
+    }
+
+    pub async fn update_course(
+        &self,
+        tutor_id: i32,
+        course_id: i32,
+        update_course: &UpdateCourse,
+    ) -> Result<Course, EzyTutorError> {
+        let request = self
+            .client()
+            .put(self.url(&format!("/courses/{}/{}", tutor_id, course_id)));
+        send(request, Some(update_course)).await
+    }
+
+    pub async fn delete_course(&self, tutor_id: i32, course_id: i32) -> Result<(), EzyTutorError> {
+        let request = self
+            .client()
+            .delete(self.url(&format!("/courses/{}/{}", tutor_id, course_id)));
+        let _: serde_json::Value = send(request, None::<&()>).await?;
+        Ok(())
+    }
+
+    // Succeeds when the tutor web service answers its liveness check
+    pub async fn check_live(&self) -> Result<(), EzyTutorError> {
+        let request = self.client().get(self.url("/health/live"));
+        let _: serde_json::Value = send(request, None::<&()>).await?;
+        Ok(())
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+// The id of the request being handled is passed on, so that the logs of the
+// tutor web service can be matched with ours.
+async fn send<B: Serialize, T: DeserializeOwned>(
+    mut request: ClientRequest,
+    body: Option<&B>,
+) -> Result<T, EzyTutorError> {
+    if let Some(id) = current_request_id() {
+        request = request.insert_header((REQUEST_ID_HEADER, id));
+    }
+
+    let url = request.get_uri().to_string();
+    let span = tracing::info_span!(
+        "backend_request",
+        method = %request.get_method(),
+        url = %url,
+        status = field::Empty,
+    );
+    let response = match body {
+        Some(body) => request.send_json(body).instrument(span.clone()).await,
+        None => request.send().instrument(span.clone()).await,
+    };
+    let mut response =
+        response.map_err(|err| EzyTutorError::BackendError(format!("{} failed: {}", url, err)))?;
+
+    let status = response.status();
+    span.record("status", status.as_u16());
+    let payload = response
+        .body()
+        .await
+        .map_err(|err| EzyTutorError::BackendError(format!("{} failed: {}", url, err)))?;
+
+    if !status.is_success() {
+        return Err(error_for_status(status, &payload));
+    }
+
+    // Answers without a body, like 204 No Content, read as null
+    let payload = if payload.is_empty() {
+        &b"null"[..]
+    } else {
+        &payload[..]
+    };
+    serde_json::from_slice(payload).map_err(|err| {
+        EzyTutorError::BackendError(format!("{} returned an unexpected body: {}", url, err))
+    })
+}
+
+// Maps an error answer of the tutor web service to the error this app shows
+// its users. Authorization failures are our own misconfiguration rather than
+// the user's fault, so they are reported as a backend problem.
+fn error_for_status(status: StatusCode, payload: &[u8]) -> EzyTutorError {
+    let message = serde_json::from_slice::<ErrorBody>(payload)
+        .map(ErrorBody::message)
+        .unwrap_or_else(|_| String::from_utf8_lossy(payload).into_owned());
+
+    match status {
+        StatusCode::NOT_FOUND => EzyTutorError::NotFound(message),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            EzyTutorError::BackendError(format!("{}: {}", status, message))
+        }
+        status if status.is_client_error() => EzyTutorError::InvalidInput(message),
+        status => EzyTutorError::BackendError(format!("{}: {}", status, message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use serde_json::json;
+    use tutor_common::request_id::request_id;
+
+    #[test]
+    fn error_status_mapping() {
+        let body = br#"{"code":"NOT_FOUND","message":"Course id not found"}"#;
+        assert!(matches!(
+            error_for_status(StatusCode::NOT_FOUND, body),
+            EzyTutorError::NotFound(msg) if msg == "Course id not found"
+        ));
+        assert!(matches!(
+            error_for_status(StatusCode::BAD_REQUEST, b"bad"),
+            EzyTutorError::InvalidInput(msg) if msg == "bad"
+        ));
+        let invalid = br#"{"code":"VALIDATION_FAILED","message":"Validation failed","fields":[{"field":"course_name","reason":"must not be blank"}]}"#;
+        assert!(matches!(
+            error_for_status(StatusCode::UNPROCESSABLE_ENTITY, invalid),
+            EzyTutorError::InvalidInput(msg) if msg == "Validation failed: course_name must not be blank"
+        ));
+        assert!(matches!(
+            error_for_status(StatusCode::FORBIDDEN, body),
+            EzyTutorError::BackendError(_)
+        ));
+        assert!(matches!(
+            error_for_status(StatusCode::INTERNAL_SERVER_ERROR, body),
+            EzyTutorError::BackendError(_)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn backend_answers_are_typed() {
+        let server = HttpServer::new(|| {
+            App::new()
+                .route(
+                    "/tutors/",
+                    // Two pages of one tutor each
+                    web::get().to(|req: HttpRequest| async move {
+                        let (tutor, next_cursor) = if req.query_string().ends_with("cursor=1") {
+                            ((2, "Frank"), json!(null))
+                        } else {
+                            ((1, "Merlene"), json!(1))
+                        };
+                        HttpResponse::Ok().json(json!({"items": [{
+                            "tutor_id": tutor.0,
+                            "tutor_name": tutor.1,
+                            "tutor_pic_url": "http://s3.amazon.aws.com/pic1",
+                            "tutor_profile": "An experienced professional"
+                        }], "total": 2, "limit": 1, "offset": 0, "next_cursor": next_cursor}))
+                    }),
+                )
+                .route(
+                    "/courses/{tutor_id}/{course_id}",
+                    web::delete().to(|path: web::Path<(i32, i32)>| async move {
+                        match path.into_inner() {
+                            (1, 1) => HttpResponse::NoContent().finish(),
+                            _ => HttpResponse::NotFound()
+                                .json(json!({"code": "NOT_FOUND", "message": "Course not found"})),
+                        }
+                    }),
+                )
+        })
+        .workers(1)
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_rt::spawn(server);
+
+        let client = EzyTutorApiClient::new(
+            &format!("http://{}/", addr),
+            Some("token".into()),
+            Duration::from_secs(5),
+        );
+        let tutors = client.list_tutors().await.unwrap();
+        let names: Vec<&str> = tutors
+            .iter()
+            .map(|tutor| tutor.tutor_name.as_str())
+            .collect();
+        assert_eq!(names, ["Merlene", "Frank"]);
+
+        let deleted = client.delete_course(1, 99).await;
+        assert!(matches!(deleted, Err(EzyTutorError::NotFound(_))));
+        client.delete_course(1, 1).await.unwrap();
+
+        handle.stop(true).await;
+    }
+
+    #[actix_rt::test]
+    async fn request_id_is_passed_on() {
+        // The backend answers with the request id it was given as tutor name
+        let server = HttpServer::new(|| {
+            App::new().route(
+                "/tutors/",
+                web::get().to(|req: HttpRequest| async move {
+                    let id = req
+                        .headers()
+                        .get(REQUEST_ID_HEADER)
+                        .and_then(|id| id.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    HttpResponse::Ok().json(json!({"items": [{
+                        "tutor_id": 1,
+                        "tutor_name": id,
+                        "tutor_pic_url": "",
+                        "tutor_profile": ""
+                    }], "total": 1, "limit": 20, "offset": 0, "next_cursor": null}))
+                }),
+            )
+        })
+        .workers(1)
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_rt::spawn(server);
+
+        let client =
+            EzyTutorApiClient::new(&format!("http://{}", addr), None, Duration::from_secs(5));
+        let app = actix_web::test::init_service(
+            App::new()
+                .app_data(web::Data::new(client))
+                .wrap(from_fn(request_id))
+                .route(
+                    "/",
+                    web::get().to(|client: web::Data<EzyTutorApiClient>| async move {
+                        let tutors = client.list_tutors().await?;
+                        Ok::<_, EzyTutorError>(
+                            HttpResponse::Ok().body(tutors[0].tutor_name.clone()),
+                        )
+                    }),
+                ),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/")
+            .insert_header((REQUEST_ID_HEADER, "registration-42"))
+            .to_request();
+        let body = actix_web::test::call_and_read_body(&app, req).await;
+        assert_eq!(&body[..], b"registration-42");
+
+        handle.stop(true).await;
+    }
+
+    #[actix_rt::test]
+    async fn unreachable_backend_is_an_error() {
+        let client = EzyTutorApiClient::new("http://127.0.0.1:1", None, Duration::from_secs(1));
+
+        let tutors = client.list_tutors().await;
+        assert!(matches!(tutors, Err(EzyTutorError::BackendError(_))));
+    }
+}
@@ -1,7 +1,14 @@
 use crate::errors::EzyTutorError;
+use crate::iter6::throttle::{FailedLogins, LoginThrottle};
 use crate::model::*;
 use sqlx::postgres::PgPool;
+use tracing::instrument;
 
+// Classes of the advisory locks taken by record_login_attempt
+const LOGIN_USERNAME_LOCK: i32 = 1;
+const LOGIN_IP_LOCK: i32 = 2;
+
+#[instrument(skip(pool))]
 pub async fn get_user_record(pool: &PgPool, username: String) -> Result<User, EzyTutorError> {
     let user_row = sqlx::query_as!(
         User,
@@ -18,15 +25,226 @@ pub async fn get_user_record(pool: &PgPool, username: String) -> Result<User, Ez
     }
 }
 
+#[instrument(skip_all)]
+pub async fn get_user_by_email(pool: &PgPool, email: String) -> Result<User, EzyTutorError> {
+    let user_row = sqlx::query_as!(
+        User,
+        "SELECT * FROM ezyweb_user WHERE lower(user_email) = lower($1)",
+        email
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(user) = user_row {
+        Ok(user)
+    } else {
+        Err(EzyTutorError::NotFound("Email not found".into()))
+    }
+}
+
+#[instrument(skip_all, fields(username = %new_user.username))]
 pub async fn post_new_user(pool: &PgPool, new_user: User) -> Result<User, EzyTutorError> {
     let user_row = sqlx::query_as!(
         User,
-        "INSERT INTO ezyweb_user (username, tutor_id, user_password) VALUES ($1, $2, $3) RETURNING username, tutor_id, user_password",
+        "INSERT INTO ezyweb_user (username, tutor_id, user_password, user_email) VALUES ($1, $2, $3, $4) RETURNING username, tutor_id, user_password, user_email",
         new_user.username,
         new_user.tutor_id,
-        new_user.user_password
+        new_user.user_password,
+        new_user.user_email
     ).fetch_one(pool)
-    .await?;
+    .await
+    .map_err(|err| {
+        EzyTutorError::from_write(
+            err,
+            &[
+                ("username", &new_user.username, USERNAME_MAX_LEN),
+                (
+                    "user_email",
+                    new_user.user_email.as_deref().unwrap_or_default(),
+                    EMAIL_MAX_LEN,
+                ),
+            ],
+        )
+    })?;
 
     Ok(user_row)
 }
+
+// Stores a new password and burns the reset tokens still outstanding for the
+// user, which were issued for the old one.
+#[instrument(skip(pool, user_password))]
+pub async fn update_user_password(
+    pool: &PgPool,
+    username: String,
+    user_password: String,
+) -> Result<(), EzyTutorError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "UPDATE ezyweb_user SET user_password = $1 WHERE username = $2",
+        user_password,
+        username
+    )
+    .execute(&mut tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE ezyweb_password_reset SET used_time = now() WHERE username = $1 AND used_time IS NULL",
+        username
+    )
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[instrument(skip(pool, token_hash))]
+pub async fn post_password_reset(
+    pool: &PgPool,
+    token_hash: String,
+    username: String,
+    valid_minutes: i32,
+) -> Result<(), EzyTutorError> {
+    sqlx::query!(
+        "INSERT INTO ezyweb_password_reset (token_hash, username, expires_time)
+        VALUES ($1, $2, now() + make_interval(mins => $3))",
+        token_hash,
+        username,
+        valid_minutes
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Redeems a reset token and stores the new password in one transaction, so a
+// token can never be used twice or be burnt without the password changing.
+// The other tokens outstanding for the user are burnt along with it.
+#[instrument(skip_all)]
+pub async fn reset_user_password(
+    pool: &PgPool,
+    token_hash: String,
+    user_password: String,
+) -> Result<String, EzyTutorError> {
+    let mut tx = pool.begin().await?;
+
+    let username = sqlx::query_scalar!(
+        "UPDATE ezyweb_password_reset SET used_time = now()
+        WHERE token_hash = $1 AND used_time IS NULL AND expires_time > now()
+        RETURNING username",
+        token_hash
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or_else(|| EzyTutorError::NotFound("Reset link is invalid or has expired".into()))?;
+
+    sqlx::query!(
+        "UPDATE ezyweb_user SET user_password = $1 WHERE username = $2",
+        user_password,
+        username
+    )
+    .execute(&mut tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE ezyweb_password_reset SET used_time = now() WHERE username = $1 AND used_time IS NULL",
+        username
+    )
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(username)
+}
+
+// Checks the throttle for a signin attempt and records the attempt in one
+// transaction. Attempts for the same username, and then from the same address,
+// take turns on advisory locks, so parallel guesses cannot all pass the check
+// before any of them is recorded. Returns the seconds to wait when the attempt
+// is refused. Allowed attempts count as failures until a successful signin
+// deletes them. Attempts older than the throttle window are purged on the way.
+#[instrument(skip(pool, throttle))]
+pub async fn record_login_attempt(
+    pool: &PgPool,
+    username: String,
+    client_ip: String,
+    throttle: &LoginThrottle,
+) -> Result<Option<i64>, EzyTutorError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+        .bind(LOGIN_USERNAME_LOCK)
+        .bind(&username)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+        .bind(LOGIN_IP_LOCK)
+        .bind(&client_ip)
+        .execute(&mut tx)
+        .await?;
+
+    sqlx::query!(
+        "DELETE FROM ezyweb_login_attempt WHERE attempt_time <= now() - make_interval(mins => $1)",
+        throttle.window_minutes
+    )
+    .execute(&mut tx)
+    .await?;
+
+    let by_username = sqlx::query!(
+        r#"SELECT count(*) as "count!", EXTRACT(EPOCH FROM now() - max(attempt_time))::BIGINT as secs_since_last
+        FROM ezyweb_login_attempt
+        WHERE username = $1"#,
+        username
+    )
+    .fetch_one(&mut tx)
+    .await?;
+
+    let by_ip = sqlx::query!(
+        r#"SELECT count(*) as "count!", EXTRACT(EPOCH FROM now() - max(attempt_time))::BIGINT as secs_since_last
+        FROM ezyweb_login_attempt
+        WHERE client_ip = $1"#,
+        client_ip
+    )
+    .fetch_one(&mut tx)
+    .await?;
+
+    let wait = throttle.retry_after(
+        &FailedLogins {
+            count: by_username.count,
+            secs_since_last: by_username.secs_since_last.unwrap_or_default(),
+        },
+        &FailedLogins {
+            count: by_ip.count,
+            secs_since_last: by_ip.secs_since_last.unwrap_or_default(),
+        },
+    );
+    if wait.is_none() {
+        sqlx::query!(
+            "INSERT INTO ezyweb_login_attempt (username, client_ip) VALUES ($1, $2)",
+            username,
+            client_ip
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(wait)
+}
+
+#[instrument(skip(pool))]
+pub async fn delete_failed_logins(pool: &PgPool, username: String) -> Result<(), EzyTutorError> {
+    sqlx::query!(
+        "DELETE FROM ezyweb_login_attempt WHERE username = $1",
+        username
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
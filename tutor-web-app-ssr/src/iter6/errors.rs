@@ -1,77 +0,0 @@
-use actix_web::{error, http::StatusCode, HttpResponse, Result};
-use serde::Serialize;
-use sqlx::error::Error as SQLxError;
-use std::fmt;
-
-#[derive(Serialize, Debug)]
-pub enum EzyTutorError {
-    DBError(String),
-    ActixError(String),
-    NotFound(String),
-    TeraError(String),
-}
-
-#[derive(Debug, Serialize)]
-pub struct MyErrorResponse {
-    error_message: String,
-}
-
-impl std::error::Error for EzyTutorError {}
-
-impl EzyTutorError {
-    fn error_response(&self) -> String {
-        match self {
-            EzyTutorError::DBError(msg) => {
-                println!("Database error ocurred: {:?}", msg);
-                "Database error".into()
-            }
-            EzyTutorError::ActixError(msg) => {
-                println!("Server error ocurred: {:?}", msg);
-                "Internal server error".into()
-            }
-            EzyTutorError::TeraError(msg) => {
-                println!("Error in rendering the template: {:?}", msg);
-                msg.into()
-            }
-            EzyTutorError::NotFound(msg) => {
-                println!("Not found error ocurred: {:?}", msg);
-                msg.into()
-            }
-        }
-    }
-}
-
-impl error::ResponseError for EzyTutorError {
-    fn status_code(&self) -> StatusCode {
-        match self {
-            EzyTutorError::DBError(_msg)
-            | EzyTutorError::ActixError(_msg)
-            | EzyTutorError::TeraError(_msg) => StatusCode::INTERNAL_SERVER_ERROR,
-            EzyTutorError::NotFound(_msg) => StatusCode::NOT_FOUND,
-        }
-    }
-
-    fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(MyErrorResponse {
-            error_message: self.error_response(),
-        })
-    }
-}
-
-impl fmt::Display for EzyTutorError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self)
-    }
-}
-
-impl From<actix_web::error::Error> for EzyTutorError {
-    fn from(err: actix_web::error::Error) -> Self {
-        EzyTutorError::ActixError(err.to_string())
-    }
-}
-
-impl From<SQLxError> for EzyTutorError {
-    fn from(err: SQLxError) -> Self {
-        EzyTutorError::DBError(err.to_string())
-    }
-}
@@ -1,10 +1,16 @@
-use crate::dbaccess::{get_user_record, post_new_user};
+use crate::dbaccess::{
+    delete_failed_logins, get_user_record, post_new_user, record_login_attempt,
+    update_user_password,
+};
 use crate::errors::EzyTutorError;
+use crate::iter6::password::{hash_password, needs_rehash, verify_password};
+use crate::iter6::session::{sign_in, sign_out, SessionSettings};
 use crate::iter6::state::AppState;
-use crate::model::{TutorRegisterForm, TutorResponse, TutorSigninForm, User};
-use actix_web::{web, Error, HttpResponse, Result};
-use argon2::{self, Config};
-use serde_json::json;
+use crate::model::{TutorRegisterForm, TutorSigninForm, User, USERNAME_MAX_LEN};
+use actix_session::Session;
+use actix_web::{http::header, web, Error, HttpRequest, HttpResponse, Result};
+use tutor_api::tutor::NewTutor;
+use tutor_common::metrics;
 
 pub async fn show_register_form(tmpl: web::Data<tera::Tera>) -> Result<HttpResponse, Error> {
     let mut ctx = tera::Context::new();
@@ -12,6 +18,7 @@ pub async fn show_register_form(tmpl: web::Data<tera::Tera>) -> Result<HttpRespo
     ctx.insert("current_username", "");
     ctx.insert("current_password", "");
     ctx.insert("current_confirmation", "");
+    ctx.insert("current_email", "");
     ctx.insert("current_name", "");
     ctx.insert("current_imageurl", "");
     ctx.insert("current_profile", "");
@@ -23,12 +30,32 @@ pub async fn show_register_form(tmpl: web::Data<tera::Tera>) -> Result<HttpRespo
     Ok(HttpResponse::Ok().content_type("text/html").body(s))
 }
 
+// Shows the register form again with the values entered so far, except for
+// the passwords
+fn render_register_error(
+    tmpl: &tera::Tera,
+    params: &TutorRegisterForm,
+    error: &str,
+) -> Result<String, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("error", error);
+    ctx.insert("current_username", &params.username);
+    ctx.insert("current_password", "");
+    ctx.insert("current_confirmation", "");
+    ctx.insert("current_email", &params.email);
+    ctx.insert("current_name", &params.name);
+    ctx.insert("current_imageurl", &params.imageurl);
+    ctx.insert("current_profile", &params.profile);
+
+    tmpl.render("register.html", &ctx)
+        .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))
+}
+
 pub async fn handle_register(
     tmpl: web::Data<tera::Tera>,
     app_state: web::Data<AppState>,
     params: web::Form<TutorRegisterForm>,
 ) -> Result<HttpResponse, Error> {
-    let mut ctx = tera::Context::new();
     let s;
     let username = params.username.clone();
     let user = get_user_record(&app_state.db, username.to_string()).await;
@@ -36,63 +63,67 @@ pub async fn handle_register(
     let user_not_found: bool = user.is_err();
 
     if user_not_found {
-        if params.password != params.confirmation {
-            ctx.insert("error", "Password do not match");
-            ctx.insert("current_username", &params.username);
-            ctx.insert("current_password", "");
-            ctx.insert("current_confirmation", "");
-            ctx.insert("current_name", &params.name);
-            ctx.insert("current_imageurl", &params.imageurl);
-            ctx.insert("current_profile", &params.profile);
-
-            s = tmpl
-                .render("register.html", &ctx)
-                .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
+        if params.username.chars().count() > USERNAME_MAX_LEN {
+            let error = format!(
+                "User id must be at most {} characters long",
+                USERNAME_MAX_LEN
+            );
+            s = render_register_error(&tmpl, &params, &error)?;
+        } else if params.password != params.confirmation {
+            s = render_register_error(&tmpl, &params, "Password do not match")?;
         } else {
-            let new_tutor = json!({
-                "tutor_name": &params.name,
-                "tutor_pic_url": &params.imageurl,
-                "tutor_profile": &params.profile
-            });
-
-            let awc_client = awc::Client::default();
-            let res = awc_client
-                .post("http://localhost:3000/tutors/")
-                .send_json(&new_tutor)
-                .await
-                .unwrap()
-                .body()
-                .await?;
-
-            let tutor_response: TutorResponse = serde_json::from_str(&std::str::from_utf8(&res)?)?;
-            s = format!("Congratulations. You have been successfully registered with Ezytutor and your tutor id is {}. To start using Ezytutor, please login with your credentials.", tutor_response.tutor_id);
-
-            let salt = b"somerandomsalt";
-            let config = Config::default();
-
-            let hash =
-                argon2::hash_encoded(params.password.clone().as_bytes(), salt, &config).unwrap();
+            let hash = hash_password(&params.password, &app_state.password_config).await?;
+
+            let new_tutor = NewTutor {
+                tutor_name: params.name.clone(),
+                tutor_pic_url: params.imageurl.clone(),
+                tutor_profile: params.profile.clone(),
+            };
+            let tutor_response = app_state.api_client.create_tutor(&new_tutor).await?;
 
             let user = User {
                 username,
                 tutor_id: tutor_response.tutor_id,
                 user_password: hash,
+                user_email: Some(params.email.clone()),
             };
 
-            let _tutor_created = post_new_user(&app_state.db, user).await?;
+            // The username may have been taken since it was looked up. A tutor without a user is deleted again, as nobody
+            // could ever sign in for it.
+            let posted = post_new_user(&app_state.db, user).await;
+            if posted.is_err() {
+                if let Err(err) = app_state
+                    .api_client
+                    .delete_tutor(tutor_response.tutor_id)
+                    .await
+                {
+                    tracing::warn!(
+                        tutor_id = tutor_response.tutor_id,
+                        "Could not delete the tutor of a failed registration: {}",
+                        err
+                    );
+                }
+            }
+            s = match posted {
+                Ok(_) => {
+                    metrics::TUTORS_REGISTERED.inc();
+                    format!("Congratulations. You have been successfully registered with Ezytutor and your tutor id is {}. To start using Ezytutor, please login with your credentials.", tutor_response.tutor_id)
+                }
+                Err(EzyTutorError::Conflict(_)) => {
+                    render_register_error(&tmpl, &params, "User id already exists")?
+                }
+                Err(EzyTutorError::ValidationFailed(fields)) => {
+                    let errors: Vec<String> = fields
+                        .iter()
+                        .map(|err| format!("{} {}", err.field, err.reason))
+                        .collect();
+                    render_register_error(&tmpl, &params, &errors.join(", "))?
+                }
+                Err(err) => return Err(err.into()),
+            };
         }
     } else {
-        ctx.insert("error", "User id already exists");
-        ctx.insert("current_username", &params.username);
-        ctx.insert("current_password", "");
-        ctx.insert("current_confirmation", "");
-        ctx.insert("current_name", &params.name);
-        ctx.insert("current_imageurl", &params.imageurl);
-        ctx.insert("current_profile", &params.profile);
-
-        s = tmpl
-            .render("register.html", &ctx)
-            .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
+        s = render_register_error(&tmpl, &params, "User id already exists")?;
     }
 
     Ok(HttpResponse::Ok().content_type("text/html").body(s))
@@ -103,7 +134,6 @@ pub async fn show_signin_form(tmpl: web::Data<tera::Tera>) -> Result<HttpRespons
 
     ctx.insert("error", "");
     ctx.insert("current_name", "");
-    ctx.insert("current_password", "");
 
     let s = tmpl
         .render("signin.html", &ctx)
@@ -115,30 +145,76 @@ pub async fn show_signin_form(tmpl: web::Data<tera::Tera>) -> Result<HttpRespons
 pub async fn handle_signin(
     tmpl: web::Data<tera::Tera>,
     app_state: web::Data<AppState>,
+    session_settings: web::Data<SessionSettings>,
+    session: Session,
+    req: HttpRequest,
     params: web::Form<TutorSigninForm>,
 ) -> Result<HttpResponse, EzyTutorError> {
     let mut ctx = tera::Context::new();
     let s;
 
     let username = params.username.clone();
+    // The peer address is used rather than X-Forwarded-For, which any client
+    // could set to dodge the per ip limit.
+    let client_ip = req
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_default();
+
+    // The attempt is recorded as failed before the password is checked, and
+    // forgotten again when it turns out right
+    let retry_after = record_login_attempt(
+        &app_state.db,
+        username.clone(),
+        client_ip,
+        &app_state.login_throttle,
+    )
+    .await?;
+    if let Some(wait) = retry_after {
+        ctx.insert(
+            "error",
+            &format!(
+                "Too many failed signin attempts, please try again in {} seconds",
+                wait
+            ),
+        );
+        ctx.insert("current_name", &params.username);
+
+        s = tmpl
+            .render("signin.html", &ctx)
+            .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
+
+        return Ok(HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, wait.to_string()))
+            .content_type("text/html")
+            .body(s));
+    }
+
     let user = get_user_record(&app_state.db, username.to_string()).await;
 
     if let Ok(user) = user {
-        let does_password_match = argon2::verify_encoded(
-            &user.user_password.trim(),
-            params.password.clone().as_bytes(),
-        )
-        .unwrap();
+        let does_password_match = verify_password(&user.user_password, &params.password).await?;
         if !does_password_match {
             ctx.insert("error", "Invalid login");
             ctx.insert("current_name", &params.username);
-            ctx.insert("current_password", &params.password);
 
             s = tmpl
                 .render("signin.html", &ctx)
                 .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
         } else {
+            delete_failed_logins(&app_state.db, username).await?;
+
+            // The password is only known in clear text right now, so this is
+            // the moment to move hashes made with older parameters to the
+            // current ones.
+            if needs_rehash(&user.user_password, &app_state.password_config) {
+                let hash = hash_password(&params.password, &app_state.password_config).await?;
+                update_user_password(&app_state.db, user.username.clone(), hash).await?;
+            }
+            sign_in(&session, &session_settings, &user)?;
+
             ctx.insert("name", &params.username);
+            ctx.insert("signed_in", &true);
             ctx.insert("title", &"Signin confirmation!".to_owned());
             ctx.insert(
                 "message",
@@ -152,7 +228,6 @@ pub async fn handle_signin(
     } else {
         ctx.insert("error", "User id not found");
         ctx.insert("current_name", &params.username);
-        ctx.insert("current_password", &params.password);
 
         s = tmpl
             .render("signin.html", &ctx)
@@ -161,3 +236,391 @@ pub async fn handle_signin(
 
     Ok(HttpResponse::Ok().content_type("text/html").body(s))
 }
+
+pub async fn handle_signout(session: Session) -> HttpResponse {
+    sign_out(&session);
+
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, "/signinform"))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter6::client::EzyTutorApiClient;
+    use crate::iter6::mail::LogMailSender;
+    use crate::iter6::password::PasswordConfig;
+    use crate::iter6::session::session_middleware;
+    use crate::iter6::throttle::LoginThrottle;
+    use actix_web::cookie::Key;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::{App, HttpServer};
+    use argon2::Variant;
+    use dotenv::dotenv;
+    use serde_json::json;
+    use sqlx::postgres::PgPool;
+    use std::env;
+    use std::sync::{Arc, Mutex};
+
+    #[actix_rt::test]
+    async fn duplicate_username_is_a_conflict() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'duplicateuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let user = || User {
+            username: "duplicateuser".into(),
+            tutor_id: 1,
+            user_password: "not a hash".into(),
+            user_email: None,
+        };
+
+        post_new_user(&pool, user()).await.unwrap();
+        let duplicate = post_new_user(&pool, user()).await;
+        assert!(matches!(duplicate, Err(EzyTutorError::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn too_long_username_is_named() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+
+        let user = User {
+            username: "a".repeat(USERNAME_MAX_LEN + 1),
+            tutor_id: 1,
+            user_password: "not a hash".into(),
+            user_email: None,
+        };
+        match post_new_user(&pool, user).await {
+            Err(EzyTutorError::ValidationFailed(fields)) => {
+                assert_eq!(fields[0].field, "username");
+                assert_eq!(fields[0].reason, "must be at most 20 characters long");
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn too_long_username_is_refused_by_the_form() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+
+        // No tutor may be created, the backend is not even there
+        let app_state = web::Data::new(AppState {
+            db: pool,
+            api_client: EzyTutorApiClient::new(
+                "http://127.0.0.1:1",
+                Some("token".into()),
+                std::time::Duration::from_secs(5),
+            ),
+            password_config: PasswordConfig::default(),
+            login_throttle: LoginThrottle::default(),
+            mailer: Arc::new(LogMailSender::new(None)),
+            public_url: "http://localhost:8080".into(),
+        });
+        let tmpl = web::Data::new(
+            tera::Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter6/**/*")).unwrap(),
+        );
+        let form = web::Form(TutorRegisterForm {
+            username: "a".repeat(USERNAME_MAX_LEN + 1),
+            password: "password".into(),
+            confirmation: "password".into(),
+            email: "longuser@example.com".into(),
+            name: "Long".into(),
+            imageurl: "http://s3.amazon.aws.com/pic1".into(),
+            profile: "Has a long name".into(),
+        });
+
+        let resp = handle_register(tmpl, app_state, form).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        assert!(
+            String::from_utf8_lossy(&body).contains("User id must be at most 20 characters long")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn tutor_of_a_lost_registration_race_is_deleted() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'raceuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // The backend creates tutor 4242, while somebody else registers
+        // raceuser in the meantime
+        let deleted_tutors = Arc::new(Mutex::new(Vec::new()));
+        let backend_pool = pool.clone();
+        let backend_deleted = deleted_tutors.clone();
+        let server = HttpServer::new(move || {
+            let pool = backend_pool.clone();
+            let deleted = backend_deleted.clone();
+            App::new()
+                .route(
+                    "/tutors/",
+                    web::post().to(move || {
+                        let pool = pool.clone();
+                        async move {
+                            sqlx::query!(
+                                "INSERT INTO ezyweb_user (username, tutor_id, user_password) VALUES ('raceuser', 1, 'hash')"
+                            )
+                            .execute(&pool)
+                            .await
+                            .unwrap();
+                            HttpResponse::Ok().json(json!({
+                                "tutor_id": 4242,
+                                "tutor_name": "Race",
+                                "tutor_pic_url": "http://s3.amazon.aws.com/pic1",
+                                "tutor_profile": "Lost a race"
+                            }))
+                        }
+                    }),
+                )
+                .route(
+                    "/tutors/{tutor_id}",
+                    web::delete().to(move |tutor_id: web::Path<i32>| {
+                        deleted.lock().unwrap().push(tutor_id.into_inner());
+                        async { HttpResponse::Ok().json("Deleted") }
+                    }),
+                )
+        })
+        .workers(1)
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_rt::spawn(server);
+
+        let app_state = web::Data::new(AppState {
+            db: pool.clone(),
+            api_client: EzyTutorApiClient::new(
+                &format!("http://{}", addr),
+                Some("token".into()),
+                std::time::Duration::from_secs(5),
+            ),
+            password_config: PasswordConfig {
+                variant: Variant::Argon2id,
+                mem_cost: 64,
+                time_cost: 1,
+                lanes: 1,
+            },
+            login_throttle: LoginThrottle::default(),
+            mailer: Arc::new(LogMailSender::new(None)),
+            public_url: "http://localhost:8080".into(),
+        });
+        let tmpl = web::Data::new(
+            tera::Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter6/**/*")).unwrap(),
+        );
+        let form = web::Form(TutorRegisterForm {
+            username: "raceuser".into(),
+            password: "password".into(),
+            confirmation: "password".into(),
+            email: "raceuser@example.com".into(),
+            name: "Race".into(),
+            imageurl: "http://s3.amazon.aws.com/pic1".into(),
+            profile: "Lost a race".into(),
+        });
+
+        let resp = handle_register(tmpl, app_state, form).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("User id already exists"));
+        assert_eq!(*deleted_tutors.lock().unwrap(), [4242]);
+
+        handle.stop(true).await;
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'raceuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn failed_signins_are_throttled() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let password_config = PasswordConfig {
+            variant: Variant::Argon2id,
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        };
+        let session_settings = SessionSettings {
+            ttl_minutes: 30,
+            cookie_secure: false,
+        };
+
+        delete_failed_logins(&pool, "throttleuser".into())
+            .await
+            .unwrap();
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'throttleuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        post_new_user(
+            &pool,
+            User {
+                username: "throttleuser".into(),
+                tutor_id: 1,
+                user_password: hash_password("rightpassword", &password_config)
+                    .await
+                    .unwrap(),
+                user_email: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let app_state = web::Data::new(AppState {
+            db: pool.clone(),
+            api_client: EzyTutorApiClient::new(
+                "http://localhost:3000",
+                None,
+                std::time::Duration::from_secs(5),
+            ),
+            password_config,
+            login_throttle: LoginThrottle::default(),
+            mailer: Arc::new(LogMailSender::new(None)),
+            public_url: "http://localhost:8080".into(),
+        });
+        let tmpl =
+            tera::Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter6/**/*")).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(tmpl))
+                .app_data(app_state)
+                .app_data(web::Data::new(session_settings))
+                .wrap(session_middleware(Key::generate(), session_settings))
+                .route("/signin", web::post().to(handle_signin)),
+        )
+        .await;
+        let signin = |password: &str| {
+            TestRequest::post()
+                .uri("/signin")
+                .peer_addr("10.1.2.3:4000".parse().unwrap())
+                .set_form(TutorSigninForm {
+                    username: "throttleuser".into(),
+                    password: password.into(),
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, signin("wrongpassword")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("wrongpassword"));
+
+        // The next try comes before the backoff delay ran out, so even the
+        // right password is refused.
+        let resp = test::call_service(&app, signin("rightpassword")).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key(header::RETRY_AFTER));
+
+        delete_failed_logins(&pool, "throttleuser".into())
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn parallel_signin_guesses_are_throttled() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        delete_failed_logins(&pool, "parallelguesser".into())
+            .await
+            .unwrap();
+
+        let guesses: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                actix_rt::spawn(async move {
+                    record_login_attempt(
+                        &pool,
+                        "parallelguesser".into(),
+                        "10.9.9.9".into(),
+                        &LoginThrottle::default(),
+                    )
+                    .await
+                    .unwrap()
+                })
+            })
+            .collect();
+        let mut allowed = 0;
+        for guess in guesses {
+            if guess.await.unwrap().is_none() {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, 1);
+
+        delete_failed_logins(&pool, "parallelguesser".into())
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn long_usernames_are_recorded() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let username = "a-username-much-longer-than-any-real-one".to_string();
+
+        let wait = record_login_attempt(
+            &pool,
+            username.clone(),
+            "10.8.8.8".into(),
+            &LoginThrottle::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(wait, None);
+
+        delete_failed_logins(&pool, username).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn old_signin_attempts_are_purged() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        sqlx::query!(
+            "INSERT INTO ezyweb_login_attempt (username, client_ip, attempt_time)
+            VALUES ('staleuser', '10.7.7.7', now() - interval '1 day')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        record_login_attempt(
+            &pool,
+            "freshuser".into(),
+            "10.7.7.8".into(),
+            &LoginThrottle::default(),
+        )
+        .await
+        .unwrap();
+
+        let stale = sqlx::query_scalar!(
+            r#"SELECT count(*) as "count!" FROM ezyweb_login_attempt WHERE username = 'staleuser'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(stale, 0);
+
+        delete_failed_logins(&pool, "freshuser".into())
+            .await
+            .unwrap();
+    }
+}
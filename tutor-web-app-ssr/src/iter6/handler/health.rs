@@ -0,0 +1,70 @@
+use crate::iter6::migrate::migrator;
+use crate::iter6::state::AppState;
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use std::collections::BTreeMap;
+use tutor_common::health::{self, ComponentHealth, HealthReport};
+
+pub async fn show_liveness() -> HttpResponse {
+    health::live()
+}
+
+// Ready once the database answers, its schema is up to date and the tutor
+// web service can be reached
+pub async fn show_readiness(app_state: web::Data<AppState>) -> HttpResponse {
+    let backend_details = json!({ "url": app_state.api_client.base_url() });
+    let backend = match app_state.api_client.check_live().await {
+        Ok(()) => ComponentHealth::up(backend_details),
+        Err(err) => ComponentHealth::down(err, backend_details),
+    };
+
+    let components = BTreeMap::from([
+        ("database", health::check_database(&app_state.db).await),
+        (
+            "migrations",
+            health::check_migrations(&app_state.db, &migrator()).await,
+        ),
+        ("backend", backend),
+    ]);
+
+    HealthReport::new(components).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter6::client::EzyTutorApiClient;
+    use crate::iter6::mail::LogMailSender;
+    use crate::iter6::password::PasswordConfig;
+    use crate::iter6::throttle::LoginThrottle;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPool;
+    use std::env;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[actix_rt::test]
+    async fn unreachable_backend_is_not_ready() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let app_state = web::Data::new(AppState {
+            db: PgPool::connect(&database_url).await.unwrap(),
+            api_client: EzyTutorApiClient::new("http://127.0.0.1:1", None, Duration::from_secs(1)),
+            password_config: PasswordConfig::default(),
+            login_throttle: LoginThrottle::default(),
+            mailer: Arc::new(LogMailSender::new(None)),
+            public_url: "http://localhost:8080".into(),
+        });
+
+        let resp = show_readiness(app_state).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = resp.into_body().try_into_bytes().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["status"], "down");
+        assert_eq!(body["components"]["backend"]["status"], "down");
+        assert_eq!(body["components"]["backend"]["url"], "http://127.0.0.1:1");
+        assert_eq!(body["components"]["database"]["status"], "up");
+    }
+}
@@ -0,0 +1,7 @@
+use crate::iter6::state::AppState;
+use actix_web::{web, HttpResponse};
+use tutor_common::metrics;
+
+pub async fn show_metrics(app_state: web::Data<AppState>) -> HttpResponse {
+    metrics::render(&app_state.db)
+}
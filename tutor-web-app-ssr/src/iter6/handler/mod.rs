@@ -1,2 +1,5 @@
 pub mod auth;
 pub mod course;
+pub mod health;
+pub mod metrics;
+pub mod password;
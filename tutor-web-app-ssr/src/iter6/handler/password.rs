@@ -0,0 +1,488 @@
+use crate::dbaccess::{
+    get_user_by_email, get_user_record, post_password_reset, record_login_attempt,
+    reset_user_password, update_user_password,
+};
+use crate::errors::EzyTutorError;
+use crate::iter6::mail::send_mail;
+use crate::iter6::password::{
+    generate_reset_token, hash_password, hash_reset_token, verify_password,
+};
+use crate::iter6::session::SessionUser;
+use crate::iter6::state::AppState;
+use crate::model::{
+    ChangePasswordForm, ForgotPasswordForm, ResetPasswordForm, ResetPasswordQuery,
+    PASSWORD_MAX_LEN, PASSWORD_MIN_LEN,
+};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+
+const RESET_TOKEN_VALID_MINUTES: i32 = 30;
+
+fn render(
+    tmpl: &tera::Tera,
+    template: &str,
+    ctx: &tera::Context,
+) -> Result<HttpResponse, EzyTutorError> {
+    let s = tmpl
+        .render(template, ctx)
+        .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(s))
+}
+
+fn render_message(
+    tmpl: &tera::Tera,
+    name: &str,
+    title: &str,
+    message: &str,
+    signed_in: bool,
+) -> Result<HttpResponse, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("name", name);
+    ctx.insert("signed_in", &signed_in);
+    ctx.insert("title", title);
+    ctx.insert("message", message);
+
+    render(tmpl, "user.html", &ctx)
+}
+
+// The forms bound the length too, but they are easily bypassed
+fn check_new_password(password: &str) -> Option<String> {
+    let len = password.chars().count();
+    if !(PASSWORD_MIN_LEN..=PASSWORD_MAX_LEN).contains(&len) {
+        return Some(format!(
+            "Password must be {} to {} characters long",
+            PASSWORD_MIN_LEN, PASSWORD_MAX_LEN
+        ));
+    }
+
+    None
+}
+
+pub async fn show_change_password_form(
+    tmpl: web::Data<tera::Tera>,
+) -> Result<HttpResponse, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("error", "");
+
+    render(&tmpl, "change_password.html", &ctx)
+}
+
+pub async fn handle_change_password(
+    tmpl: web::Data<tera::Tera>,
+    app_state: web::Data<AppState>,
+    user: web::ReqData<SessionUser>,
+    params: web::Form<ChangePasswordForm>,
+) -> Result<HttpResponse, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    let stored = get_user_record(&app_state.db, user.username.clone()).await?;
+
+    if !verify_password(&stored.user_password, &params.current_password).await? {
+        ctx.insert("error", "Current password is not correct");
+        return render(&tmpl, "change_password.html", &ctx);
+    }
+    if params.new_password != params.confirmation {
+        ctx.insert("error", "Password do not match");
+        return render(&tmpl, "change_password.html", &ctx);
+    }
+    if let Some(error) = check_new_password(&params.new_password) {
+        ctx.insert("error", &error);
+        return render(&tmpl, "change_password.html", &ctx);
+    }
+
+    let hash = hash_password(&params.new_password, &app_state.password_config).await?;
+    update_user_password(&app_state.db, user.username.clone(), hash).await?;
+
+    render_message(
+        &tmpl,
+        &user.username,
+        "Password changed",
+        "Your password has been changed",
+        true,
+    )
+}
+
+pub async fn show_forgot_password_form(
+    tmpl: web::Data<tera::Tera>,
+) -> Result<HttpResponse, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("error", "");
+
+    render(&tmpl, "forgot_password.html", &ctx)
+}
+
+pub async fn handle_forgot_password(
+    tmpl: web::Data<tera::Tera>,
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+    params: web::Form<ForgotPasswordForm>,
+) -> Result<HttpResponse, EzyTutorError> {
+    // Every request is throttled like a failed signin, keyed on the email and
+    // the client ip, so the form cannot be used to flood a mailbox. The
+    // prefix keeps these apart from the signin attempts.
+    let client_ip = req
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_default();
+    let retry_after = record_login_attempt(
+        &app_state.db,
+        format!("reset:{}", params.email.trim().to_lowercase()),
+        format!("reset:{}", client_ip),
+        &app_state.login_throttle,
+    )
+    .await?;
+    if let Some(wait) = retry_after {
+        let mut ctx = tera::Context::new();
+        ctx.insert(
+            "error",
+            &format!(
+                "Too many password reset requests, please try again in {} seconds",
+                wait
+            ),
+        );
+        let s = tmpl
+            .render("forgot_password.html", &ctx)
+            .map_err(|_| EzyTutorError::TeraError("Template error".to_string()))?;
+
+        return Ok(HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, wait.to_string()))
+            .content_type("text/html")
+            .body(s));
+    }
+
+    // Whether the email is known or not, the answer is the same so the form
+    // cannot be used to find out who has an account.
+    if let Ok(user) = get_user_by_email(&app_state.db, params.email.clone()).await {
+        let token = generate_reset_token();
+        post_password_reset(
+            &app_state.db,
+            hash_reset_token(&token),
+            user.username.clone(),
+            RESET_TOKEN_VALID_MINUTES,
+        )
+        .await?;
+
+        let body = format!(
+            "Hi {},\n\nUse the link below to choose a new EzyTutor password. It is valid for {} minutes and can be used once.\n\n{}/resetpassword?token={}\n\nIf you did not ask for a new password you can ignore this mail.",
+            user.username, RESET_TOKEN_VALID_MINUTES, app_state.public_url, token
+        );
+        let to = user.user_email.unwrap_or_default();
+        send_mail(
+            app_state.mailer.clone(),
+            &to,
+            "Reset your EzyTutor password",
+            &body,
+        )
+        .await?;
+    }
+
+    render_message(
+        &tmpl,
+        "",
+        "Check your mail",
+        "If an account exists for this email, a link to reset the password has been sent to it",
+        false,
+    )
+}
+
+pub async fn show_reset_password_form(
+    tmpl: web::Data<tera::Tera>,
+    query: web::Query<ResetPasswordQuery>,
+) -> Result<HttpResponse, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("error", "");
+    ctx.insert("token", &query.token);
+
+    render(&tmpl, "reset_password.html", &ctx)
+}
+
+pub async fn handle_reset_password(
+    tmpl: web::Data<tera::Tera>,
+    app_state: web::Data<AppState>,
+    params: web::Form<ResetPasswordForm>,
+) -> Result<HttpResponse, EzyTutorError> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("token", &params.token);
+
+    if params.new_password != params.confirmation {
+        ctx.insert("error", "Password do not match");
+        return render(&tmpl, "reset_password.html", &ctx);
+    }
+    if let Some(error) = check_new_password(&params.new_password) {
+        ctx.insert("error", &error);
+        return render(&tmpl, "reset_password.html", &ctx);
+    }
+
+    let hash = hash_password(&params.new_password, &app_state.password_config).await?;
+    match reset_user_password(&app_state.db, hash_reset_token(&params.token), hash).await {
+        Ok(username) => render_message(
+            &tmpl,
+            &username,
+            "Password reset",
+            "Your password has been reset, you can now sign in with it",
+            false,
+        ),
+        Err(EzyTutorError::NotFound(msg)) => {
+            ctx.insert("error", &msg);
+            render(&tmpl, "reset_password.html", &ctx)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbaccess::{post_new_user, update_user_password};
+    use crate::iter6::client::EzyTutorApiClient;
+    use crate::iter6::mail::MailSender;
+    use crate::iter6::password::PasswordConfig;
+    use crate::model::User;
+    use actix_web::http::StatusCode;
+    use actix_web::test;
+    use argon2::Variant;
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPool;
+    use std::env;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct RecordingMailSender {
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl MailSender for RecordingMailSender {
+        fn send(&self, _to: &str, _subject: &str, body: &str) -> Result<(), EzyTutorError> {
+            self.sent.lock().unwrap().push(body.to_string());
+            Ok(())
+        }
+    }
+
+    const TEST_CONFIG: PasswordConfig = PasswordConfig {
+        variant: Variant::Argon2id,
+        mem_cost: 64,
+        time_cost: 1,
+        lanes: 1,
+    };
+
+    fn app_state(pool: PgPool, mailer: Arc<RecordingMailSender>) -> web::Data<AppState> {
+        web::Data::new(AppState {
+            db: pool,
+            api_client: EzyTutorApiClient::new(
+                "http://localhost:3000",
+                None,
+                std::time::Duration::from_secs(5),
+            ),
+            password_config: TEST_CONFIG,
+            login_throttle: Default::default(),
+            mailer,
+            public_url: "http://localhost:8080".into(),
+        })
+    }
+
+    fn templates() -> web::Data<tera::Tera> {
+        web::Data::new(
+            tera::Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter6/**/*")).unwrap(),
+        )
+    }
+
+    async fn forget_reset_requests(pool: &PgPool, email: &str, client_ip: &str) {
+        sqlx::query!(
+            "DELETE FROM ezyweb_login_attempt WHERE username = $1 OR client_ip = $2",
+            format!("reset:{}", email),
+            format!("reset:{}", client_ip)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn reset_token_works_only_once() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let config = TEST_CONFIG;
+
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'resetuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        post_new_user(
+            &pool,
+            User {
+                username: "resetuser".into(),
+                tutor_id: 1,
+                user_password: hash_password("oldpassword", &config).await.unwrap(),
+                user_email: Some("resetuser@example.com".into()),
+            },
+        )
+        .await
+        .unwrap();
+
+        let mailer = Arc::new(RecordingMailSender::default());
+        let app_state = app_state(pool.clone(), mailer.clone());
+        let tmpl = templates();
+
+        forget_reset_requests(&pool, "resetuser@example.com", "10.8.0.1").await;
+        let form = web::Form(ForgotPasswordForm {
+            email: "ResetUser@example.com".into(),
+        });
+        let req = test::TestRequest::default()
+            .peer_addr("10.8.0.1:4000".parse().unwrap())
+            .to_http_request();
+        let resp = handle_forgot_password(tmpl.clone(), app_state.clone(), req, form)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let mail = mailer.sent.lock().unwrap().pop().unwrap();
+        let token = mail.split("token=").nth(1).unwrap()[..40].to_string();
+
+        let new_hash = hash_password("newpassword", &config).await.unwrap();
+        reset_user_password(&pool, hash_reset_token(&token), new_hash.clone())
+            .await
+            .unwrap();
+        let user = get_user_record(&pool, "resetuser".into()).await.unwrap();
+        assert!(verify_password(&user.user_password, "newpassword")
+            .await
+            .unwrap());
+
+        let reused = reset_user_password(&pool, hash_reset_token(&token), new_hash).await;
+        assert!(matches!(reused, Err(EzyTutorError::NotFound(_))));
+    }
+
+    #[actix_rt::test]
+    async fn reset_requests_are_throttled() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let mailer = Arc::new(RecordingMailSender::default());
+        let app_state = app_state(pool.clone(), mailer);
+        let tmpl = templates();
+
+        forget_reset_requests(&pool, "flooded@example.com", "10.8.0.2").await;
+        let request = || {
+            let req = test::TestRequest::default()
+                .peer_addr("10.8.0.2:4000".parse().unwrap())
+                .to_http_request();
+            let form = web::Form(ForgotPasswordForm {
+                email: "Flooded@example.com".into(),
+            });
+            handle_forgot_password(tmpl.clone(), app_state.clone(), req, form)
+        };
+
+        let first = request().await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let second = request().await.unwrap();
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(second.headers().contains_key(header::RETRY_AFTER));
+
+        forget_reset_requests(&pool, "flooded@example.com", "10.8.0.2").await;
+    }
+
+    #[actix_rt::test]
+    async fn new_password_length_is_checked() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let mailer = Arc::new(RecordingMailSender::default());
+        let app_state = app_state(pool, mailer);
+        let tmpl = templates();
+
+        for password in ["short", "muchtoolongpassword"] {
+            let form = web::Form(ResetPasswordForm {
+                token: "sometoken".into(),
+                new_password: password.into(),
+                confirmation: password.into(),
+            });
+            let resp = handle_reset_password(tmpl.clone(), app_state.clone(), form)
+                .await
+                .unwrap();
+            let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+            assert!(std::str::from_utf8(&body)
+                .unwrap()
+                .contains("Password must be 8 to 12 characters long"));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn new_passwords_burn_outstanding_tokens() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'burnuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        post_new_user(
+            &pool,
+            User {
+                username: "burnuser".into(),
+                tutor_id: 1,
+                user_password: "oldhash".into(),
+                user_email: Some("burnuser@example.com".into()),
+            },
+        )
+        .await
+        .unwrap();
+
+        // Redeeming one token burns the others
+        for token in ["burntoken1", "burntoken2"] {
+            post_password_reset(&pool, hash_reset_token(token), "burnuser".into(), 30)
+                .await
+                .unwrap();
+        }
+        reset_user_password(&pool, hash_reset_token("burntoken1"), "newhash".into())
+            .await
+            .unwrap();
+        let other =
+            reset_user_password(&pool, hash_reset_token("burntoken2"), "newhash".into()).await;
+        assert!(matches!(other, Err(EzyTutorError::NotFound(_))));
+
+        // So does changing the password while signed in
+        post_password_reset(&pool, hash_reset_token("burntoken3"), "burnuser".into(), 30)
+            .await
+            .unwrap();
+        update_user_password(&pool, "burnuser".into(), "otherhash".into())
+            .await
+            .unwrap();
+        let stale =
+            reset_user_password(&pool, hash_reset_token("burntoken3"), "newhash".into()).await;
+        assert!(matches!(stale, Err(EzyTutorError::NotFound(_))));
+
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'burnuser'")
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn emails_are_unique_regardless_of_case() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username IN ('caseuser1', 'caseuser2')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let user = |username: &str, email: &str| User {
+            username: username.into(),
+            tutor_id: 1,
+            user_password: "hash".into(),
+            user_email: Some(email.into()),
+        };
+        post_new_user(&pool, user("caseuser1", "caseuser@example.com"))
+            .await
+            .unwrap();
+        let taken = post_new_user(&pool, user("caseuser2", "CaseUser@Example.com")).await;
+        assert!(matches!(taken, Err(EzyTutorError::Conflict(_))));
+
+        sqlx::query!("DELETE FROM ezyweb_user WHERE username = 'caseuser1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
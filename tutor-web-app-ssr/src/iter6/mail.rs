@@ -0,0 +1,81 @@
+use crate::errors::EzyTutorError;
+use actix_web::web;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// Delivers mails to users. Plug in an SMTP or mail API backed sender for
+// production; LogMailSender is meant for local development.
+pub trait MailSender: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), EzyTutorError>;
+}
+
+// Senders may block on files or sockets, so mails are sent on the blocking
+// thread pool rather than on the worker running the handler.
+pub async fn send_mail(
+    mailer: Arc<dyn MailSender>,
+    to: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), EzyTutorError> {
+    let to = to.to_string();
+    let subject = subject.to_string();
+    let body = body.to_string();
+
+    web::block(move || mailer.send(&to, &subject, &body))
+        .await
+        .map_err(|err| EzyTutorError::ActixError(err.to_string()))?
+}
+
+// Appends every mail to the file in MAIL_OUTBOX. Without an outbox only the
+// recipient and subject are logged, as bodies may carry reset links.
+pub struct LogMailSender {
+    outbox: Option<PathBuf>,
+}
+
+impl LogMailSender {
+    pub fn new(outbox: Option<PathBuf>) -> Self {
+        LogMailSender { outbox }
+    }
+}
+
+impl MailSender for LogMailSender {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), EzyTutorError> {
+        let mail = format!("To: {}\nSubject: {}\n\n{}\n\n", to, subject, body);
+
+        match &self.outbox {
+            Some(path) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(mail.as_bytes()))
+                .map_err(|err| EzyTutorError::ActixError(err.to_string())),
+            None => {
+                tracing::info!(%to, %subject, "Mail not delivered, MAIL_OUTBOX is not set");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn log_mail_sender_appends_to_outbox() {
+        let outbox = std::env::temp_dir().join(format!("ezytutor-outbox-{}", std::process::id()));
+        let _ = fs::remove_file(&outbox);
+        let sender = LogMailSender::new(Some(outbox.clone()));
+
+        sender.send("tutor@example.com", "First", "one").unwrap();
+        sender.send("tutor@example.com", "Second", "two").unwrap();
+
+        let content = fs::read_to_string(&outbox).unwrap();
+        assert!(content.contains("To: tutor@example.com\nSubject: First\n\none"));
+        assert!(content.contains("Subject: Second"));
+        fs::remove_file(outbox).unwrap();
+    }
+}
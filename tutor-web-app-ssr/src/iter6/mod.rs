@@ -1,6 +1,13 @@
+pub mod client;
 pub mod dbaccess;
-pub mod errors;
+pub use tutor_common::errors;
 pub mod handler;
+pub mod mail;
+pub mod migrate;
 pub mod model;
+pub mod password;
 pub mod routes;
+pub mod session;
+pub mod settings;
 pub mod state;
+pub mod throttle;
@@ -0,0 +1,127 @@
+use crate::errors::EzyTutorError;
+use actix_web::web;
+use argon2::Variant;
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use tutor_common::settings::Argon2Settings;
+
+const SALT_LENGTH: usize = 16;
+const RESET_TOKEN_LENGTH: usize = 40;
+
+// The Argon2 parameters new hashes are made with, taken from the settings
+pub type PasswordConfig = Argon2Settings;
+
+// Argon2 is slow on purpose, so hashes are computed and verified on the
+// blocking thread pool instead of stalling the worker serving other requests.
+pub async fn hash_password(
+    password: &str,
+    config: &PasswordConfig,
+) -> Result<String, EzyTutorError> {
+    let password = password.to_string();
+    let argon2_config = config.argon2_config();
+
+    web::block(move || {
+        let salt: [u8; SALT_LENGTH] = rand::random();
+        argon2::hash_encoded(password.as_bytes(), &salt, &argon2_config)
+    })
+    .await
+    .map_err(|err| EzyTutorError::ActixError(err.to_string()))?
+    .map_err(|err| EzyTutorError::ActixError(err.to_string()))
+}
+
+pub async fn verify_password(encoded: &str, password: &str) -> Result<bool, EzyTutorError> {
+    let encoded = encoded.trim().to_string();
+    let password = password.to_string();
+
+    web::block(move || argon2::verify_encoded(&encoded, password.as_bytes()))
+        .await
+        .map_err(|err| EzyTutorError::ActixError(err.to_string()))?
+        .map_err(|err| EzyTutorError::ActixError(err.to_string()))
+}
+
+pub fn generate_reset_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(RESET_TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash_reset_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+// Tells whether a stored hash was produced with other parameters than the
+// current ones, e.g. the old argon2i default or a lower memory cost. Such
+// hashes are upgraded the next time the user signs in.
+pub fn needs_rehash(encoded: &str, config: &PasswordConfig) -> bool {
+    parse_encoded_config(encoded.trim()) != Some(*config)
+}
+
+// Reads the parameters out of a PHC string like
+// $argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>
+fn parse_encoded_config(encoded: &str) -> Option<PasswordConfig> {
+    let mut parts = encoded.split('$').skip(1);
+    let variant = Variant::from_str(parts.next()?).ok()?;
+    if parts.next()? != "v=19" {
+        return None;
+    }
+
+    let mut config = PasswordConfig {
+        variant,
+        mem_cost: 0,
+        time_cost: 0,
+        lanes: 0,
+    };
+    for param in parts.next()?.split(',') {
+        let (name, value) = param.split_once('=')?;
+        let value = value.parse().ok()?;
+        match name {
+            "m" => config.mem_cost = value,
+            "t" => config.time_cost = value,
+            "p" => config.lanes = value,
+            _ => return None,
+        }
+    }
+
+    Some(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::Config;
+
+    const FAST: PasswordConfig = PasswordConfig {
+        variant: Variant::Argon2id,
+        mem_cost: 64,
+        time_cost: 1,
+        lanes: 1,
+    };
+
+    #[actix_rt::test]
+    async fn same_password_gets_different_hashes() {
+        let first = hash_password("secret", &FAST).await.unwrap();
+        let second = hash_password("secret", &FAST).await.unwrap();
+
+        assert_ne!(first, second);
+        assert!(verify_password(&first, "secret").await.unwrap());
+        assert!(verify_password(&second, "secret").await.unwrap());
+        assert!(!verify_password(&first, "wrong").await.unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn hashes_with_outdated_parameters_need_rehash() {
+        let legacy =
+            argon2::hash_encoded(b"secret", b"somerandomsalt", &Config::default()).unwrap();
+        assert!(needs_rehash(&legacy, &FAST));
+
+        let stronger = PasswordConfig {
+            mem_cost: 128,
+            ..FAST
+        };
+        let current = hash_password("secret", &FAST).await.unwrap();
+        assert!(!needs_rehash(&current, &FAST));
+        assert!(needs_rehash(&current, &stronger));
+    }
+}
@@ -1,6 +1,16 @@
-use super::handler::auth::{handle_register, handle_signin, show_register_form, show_signin_form};
+use super::handler::auth::{
+    handle_register, handle_signin, handle_signout, show_register_form, show_signin_form,
+};
 use super::handler::course::{handle_delete_course, handle_insert_course, handle_update_course};
+use super::handler::health::{show_liveness, show_readiness};
+use super::handler::metrics::show_metrics;
+use super::handler::password::{
+    handle_change_password, handle_forgot_password, handle_reset_password,
+    show_change_password_form, show_forgot_password_form, show_reset_password_form,
+};
+use super::session::require_signed_in;
 use actix_files as fs;
+use actix_web::middleware::from_fn;
 use actix_web::web;
 
 pub fn app_config(config: &mut web::ServiceConfig) {
@@ -10,20 +20,44 @@ pub fn app_config(config: &mut web::ServiceConfig) {
             .service(web::resource("/").route(web::get().to(show_register_form)))
             .service(web::resource("/signinform").route(web::get().to(show_signin_form)))
             .service(web::resource("/signin").route(web::post().to(handle_signin)))
-            .service(web::resource("/register").route(web::post().to(handle_register))),
+            .service(web::resource("/signout").route(web::post().to(handle_signout)))
+            .service(web::resource("/register").route(web::post().to(handle_register)))
+            .service(web::resource("/metrics").route(web::get().to(show_metrics)))
+            .service(web::resource("/health/live").route(web::get().to(show_liveness)))
+            .service(web::resource("/health/ready").route(web::get().to(show_readiness)))
+            .service(
+                web::resource("/forgotpassword")
+                    .route(web::get().to(show_forgot_password_form))
+                    .route(web::post().to(handle_forgot_password)),
+            )
+            .service(
+                web::resource("/resetpassword")
+                    .route(web::get().to(show_reset_password_form))
+                    .route(web::post().to(handle_reset_password)),
+            ),
+    );
+}
+
+pub fn account_config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/account")
+            .wrap(from_fn(require_signed_in))
+            .service(
+                web::resource("/password")
+                    .route(web::get().to(show_change_password_form))
+                    .route(web::post().to(handle_change_password)),
+            ),
     );
 }
 
 pub fn course_config(config: &mut web::ServiceConfig) {
     config.service(
         web::scope("/courses")
-            .service(web::resource("new/{tutor_id}").route(web::post().to(handle_insert_course)))
-            .service(
-                web::resource("{tutor_id}/{course_id}").route(web::put().to(handle_update_course)),
-            )
+            .wrap(from_fn(require_signed_in))
+            .service(web::resource("new").route(web::post().to(handle_insert_course)))
+            .service(web::resource("{course_id}").route(web::put().to(handle_update_course)))
             .service(
-                web::resource("delete/{tutor_id}/{course_id}")
-                    .route(web::delete().to(handle_delete_course)),
+                web::resource("delete/{course_id}").route(web::delete().to(handle_delete_course)),
             ),
     );
 }
@@ -0,0 +1,208 @@
+use crate::errors::EzyTutorError;
+use crate::model::User;
+use actix_session::config::{CookieContentSecurity, PersistentSession};
+use actix_session::storage::CookieSessionStore;
+use actix_session::{Session, SessionExt, SessionMiddleware};
+use actix_web::body::MessageBody;
+use actix_web::cookie::{time::Duration, Key};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+const SESSION_USER_KEY: &str = "signed_in_tutor";
+
+// What the session cookie remembers about a signed in tutor. The cookie is
+// encrypted, so its content can neither be read nor forged by the browser.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionUser {
+    pub username: String,
+    pub tutor_id: i32,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SessionSettings {
+    pub ttl_minutes: i64,
+    pub cookie_secure: bool,
+}
+
+pub fn session_middleware(
+    key: Key,
+    settings: SessionSettings,
+) -> SessionMiddleware<CookieSessionStore> {
+    SessionMiddleware::builder(CookieSessionStore::default(), key)
+        .cookie_name("ezytutor_session".to_string())
+        .cookie_content_security(CookieContentSecurity::Private)
+        .cookie_secure(settings.cookie_secure)
+        .session_lifecycle(
+            PersistentSession::default().session_ttl(Duration::minutes(settings.ttl_minutes)),
+        )
+        .build()
+}
+
+fn expires_at(settings: &SessionSettings) -> i64 {
+    Utc::now().timestamp() + settings.ttl_minutes * 60
+}
+
+pub fn sign_in(
+    session: &Session,
+    settings: &SessionSettings,
+    user: &User,
+) -> Result<(), EzyTutorError> {
+    // a fresh session id on every signin prevents session fixation
+    session.renew();
+    session
+        .insert(
+            SESSION_USER_KEY,
+            SessionUser {
+                username: user.username.clone(),
+                tutor_id: user.tutor_id,
+                expires_at: expires_at(settings),
+            },
+        )
+        .map_err(|err| EzyTutorError::ActixError(err.to_string()))
+}
+
+pub fn sign_out(session: &Session) {
+    session.purge();
+}
+
+// Looks up the signed in tutor, dropping sessions that outlived their ttl.
+// The expiry is also enforced here since the cookie max-age is only a hint to
+// the browser.
+pub fn signed_in_user(session: &Session) -> Option<SessionUser> {
+    let user = session
+        .get::<SessionUser>(SESSION_USER_KEY)
+        .ok()
+        .flatten()?;
+
+    if user.expires_at <= Utc::now().timestamp() {
+        session.purge();
+        return None;
+    }
+
+    Some(user)
+}
+
+// Guards routes that need a signed in tutor. The session is extended on
+// every request and the tutor is made available to handlers through
+// `web::ReqData<SessionUser>`.
+pub async fn require_signed_in(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let settings = req
+        .app_data::<actix_web::web::Data<SessionSettings>>()
+        .map(|settings| *settings.get_ref())
+        .ok_or_else(|| EzyTutorError::ActixError("Session settings are not configured".into()))?;
+
+    let session = req.get_session();
+    let mut user = signed_in_user(&session)
+        .ok_or_else(|| EzyTutorError::Unauthorized("Please sign in first".into()))?;
+
+    user.expires_at = expires_at(&settings);
+    session
+        .insert(SESSION_USER_KEY, &user)
+        .map_err(|err| EzyTutorError::ActixError(err.to_string()))?;
+
+    req.extensions_mut().insert(user);
+    next.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    const SETTINGS: SessionSettings = SessionSettings {
+        ttl_minutes: 30,
+        cookie_secure: false,
+    };
+
+    async fn test_signin(session: Session) -> Result<HttpResponse, EzyTutorError> {
+        let user = User {
+            username: "tutor1".into(),
+            tutor_id: 7,
+            user_password: "".into(),
+            user_email: None,
+        };
+        sign_in(&session, &SETTINGS, &user)?;
+        Ok(HttpResponse::Ok().finish())
+    }
+
+    async fn test_signout(session: Session) -> HttpResponse {
+        sign_out(&session);
+        HttpResponse::Ok().finish()
+    }
+
+    async fn whoami(user: web::ReqData<SessionUser>) -> HttpResponse {
+        HttpResponse::Ok().body(user.tutor_id.to_string())
+    }
+
+    #[actix_rt::test]
+    async fn guarded_routes_need_a_session() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(SETTINGS))
+                .wrap(session_middleware(Key::generate(), SETTINGS))
+                .route("/signin", web::post().to(test_signin))
+                .route("/signout", web::post().to(test_signout))
+                .service(
+                    web::scope("/courses")
+                        .wrap(from_fn(require_signed_in))
+                        .route("/whoami", web::get().to(whoami)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/courses/whoami").to_request();
+        let err = test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let req = TestRequest::post().uri("/signin").to_request();
+        let resp = test::call_service(&app, req).await;
+        let cookie = resp
+            .response()
+            .cookies()
+            .find(|cookie| cookie.name() == "ezytutor_session")
+            .unwrap()
+            .into_owned();
+
+        let req = TestRequest::get()
+            .uri("/courses/whoami")
+            .cookie(cookie.clone())
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "7");
+
+        let req = TestRequest::post()
+            .uri("/signout")
+            .cookie(cookie)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let removal = resp
+            .response()
+            .cookies()
+            .find(|cookie| cookie.name() == "ezytutor_session")
+            .unwrap()
+            .into_owned();
+
+        let req = TestRequest::get()
+            .uri("/courses/whoami")
+            .cookie(removal)
+            .to_request();
+        let err = test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use super::session::SessionSettings;
+use super::throttle::{LoginThrottle, ThrottlePolicy};
+use std::path::PathBuf;
+use tutor_common::settings::{Argon2Settings, CommonSettings, Config, ConfigError};
+
+const DEFAULTS: &[(&str, &str)] = &[
+    ("server.bind", "127.0.0.1:8080"),
+    ("backend.url", "http://localhost:3000"),
+    ("backend.timeout_secs", "10"),
+    (
+        "templates.dir",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/static/iter6"),
+    ),
+    ("session.ttl_minutes", "30"),
+    ("session.cookie_secure", "false"),
+];
+
+// The tutor web service this app stores tutors and courses in
+#[derive(Debug, Clone)]
+pub struct BackendSettings {
+    pub url: String,
+    // Bearer token of the app, needed for every mutating call
+    pub token: String,
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub common: CommonSettings,
+    pub backend: BackendSettings,
+    // Directory holding the tera templates
+    pub templates_dir: PathBuf,
+    // Signs and encrypts the session cookie. Without it a random key is
+    // used, which signs everybody out whenever the server restarts.
+    pub session_key: Option<String>,
+    pub session: SessionSettings,
+    // File mails are appended to, they are logged when unset
+    pub mail_outbox: Option<PathBuf>,
+    // Where users reach this app, used to build links in mails
+    pub public_url: String,
+    pub argon2: Argon2Settings,
+    pub login_throttle: LoginThrottle,
+}
+
+pub fn load() -> Result<Settings, ConfigError> {
+    let mut config = Config::load(DEFAULTS)?;
+    let settings = read(&mut config);
+    config.finish(settings)
+}
+
+fn read(config: &mut Config) -> Settings {
+    let common = CommonSettings::read(config);
+
+    let backend = BackendSettings {
+        url: config.required("backend.url"),
+        token: config.required("backend.token"),
+        timeout_secs: config.required("backend.timeout_secs"),
+    };
+    if !is_http_url(&backend.url) {
+        config.invalid("backend.url", "must be an http(s) url");
+    }
+    if backend.timeout_secs == 0 {
+        config.invalid("backend.timeout_secs", "must be at least 1");
+    }
+
+    let templates_dir: PathBuf = config.required("templates.dir");
+    if !templates_dir.is_dir() {
+        config.invalid("templates.dir", "is not a directory");
+    }
+
+    let session_key: Option<String> = config.optional("session.key");
+    if session_key.as_ref().is_some_and(|key| key.len() < 64) {
+        config.invalid("session.key", "must be at least 64 bytes long");
+    }
+    let session = SessionSettings {
+        ttl_minutes: config.required("session.ttl_minutes"),
+        cookie_secure: config.required("session.cookie_secure"),
+    };
+    if session.ttl_minutes <= 0 {
+        config.invalid("session.ttl_minutes", "must be at least 1");
+    }
+
+    let public_url = config
+        .optional("public_url")
+        .unwrap_or_else(|| format!("http://{}", common.server.bind));
+    if !is_http_url(&public_url) {
+        config.invalid("public_url", "must be an http(s) url");
+    }
+
+    Settings {
+        common,
+        backend,
+        templates_dir,
+        session_key,
+        session,
+        mail_outbox: config.optional("mail.outbox"),
+        public_url,
+        argon2: Argon2Settings::read(config),
+        login_throttle: read_login_throttle(config),
+    }
+}
+
+fn read_login_throttle(config: &mut Config) -> LoginThrottle {
+    let default = LoginThrottle::default();
+
+    let window_minutes = config
+        .optional("throttle.window_minutes")
+        .unwrap_or(default.window_minutes);
+    if window_minutes < 1 {
+        config.invalid("throttle.window_minutes", "must be at least 1");
+    }
+
+    LoginThrottle {
+        window_minutes,
+        per_username: read_throttle_policy(config, "throttle.username", default.per_username),
+        per_ip: read_throttle_policy(config, "throttle.ip", default.per_ip),
+    }
+}
+
+fn read_throttle_policy(
+    config: &mut Config,
+    prefix: &str,
+    default: ThrottlePolicy,
+) -> ThrottlePolicy {
+    let key = |name: &str| format!("{}.{}", prefix, name);
+
+    let policy = ThrottlePolicy {
+        base_delay_secs: config
+            .optional(&key("base_delay_secs"))
+            .unwrap_or(default.base_delay_secs),
+        max_delay_secs: config
+            .optional(&key("max_delay_secs"))
+            .unwrap_or(default.max_delay_secs),
+        lockout_threshold: config
+            .optional(&key("lockout_threshold"))
+            .unwrap_or(default.lockout_threshold),
+        lockout_secs: config
+            .optional(&key("lockout_secs"))
+            .unwrap_or(default.lockout_secs),
+    };
+    if policy.base_delay_secs < 0 {
+        config.invalid(&key("base_delay_secs"), "must not be negative");
+    }
+    if policy.max_delay_secs < policy.base_delay_secs {
+        config.invalid(
+            &key("max_delay_secs"),
+            &format!("must not be less than {}", key("base_delay_secs")),
+        );
+    }
+    if policy.lockout_threshold < 1 {
+        config.invalid(&key("lockout_threshold"), "must be at least 1");
+    }
+    if policy.lockout_secs < 0 {
+        config.invalid(&key("lockout_secs"), "must not be negative");
+    }
+    policy
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
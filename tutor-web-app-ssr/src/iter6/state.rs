@@ -1,5 +1,16 @@
+use super::client::EzyTutorApiClient;
+use super::mail::MailSender;
+use super::password::PasswordConfig;
+use super::throttle::LoginThrottle;
 use sqlx::postgres::PgPool;
+use std::sync::Arc;
 
 pub struct AppState {
     pub db: PgPool,
+    pub api_client: EzyTutorApiClient,
+    pub password_config: PasswordConfig,
+    pub login_throttle: LoginThrottle,
+    pub mailer: Arc<dyn MailSender>,
+    // Where users reach this app, used to build links in mails
+    pub public_url: String,
 }
@@ -0,0 +1,143 @@
+// Exponential backoff and lockout for failed signins. Failures are counted
+// both per username and per client ip, the ip limit being higher since
+// several tutors may share an address.
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottlePolicy {
+    // Delay after the first failure, doubled with every further failure
+    pub base_delay_secs: i64,
+    pub max_delay_secs: i64,
+    // Number of failures after which signin is refused for lockout_secs
+    pub lockout_threshold: i64,
+    pub lockout_secs: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoginThrottle {
+    // Only failures this recent are taken into account
+    pub window_minutes: i32,
+    pub per_username: ThrottlePolicy,
+    pub per_ip: ThrottlePolicy,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        LoginThrottle {
+            window_minutes: 15,
+            per_username: ThrottlePolicy {
+                base_delay_secs: 1,
+                max_delay_secs: 60,
+                lockout_threshold: 5,
+                lockout_secs: 15 * 60,
+            },
+            per_ip: ThrottlePolicy {
+                base_delay_secs: 1,
+                max_delay_secs: 60,
+                lockout_threshold: 20,
+                lockout_secs: 15 * 60,
+            },
+        }
+    }
+}
+
+// Failed signins recorded within the throttle window
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FailedLogins {
+    pub count: i64,
+    pub secs_since_last: i64,
+}
+
+impl ThrottlePolicy {
+    // Seconds the client has to wait before trying again, None when a signin
+    // attempt is allowed right away.
+    pub fn retry_after(&self, failures: &FailedLogins) -> Option<i64> {
+        if failures.count == 0 {
+            return None;
+        }
+
+        let delay = if failures.count >= self.lockout_threshold {
+            self.lockout_secs
+        } else {
+            let exponent = (failures.count - 1).min(32) as u32;
+            self.base_delay_secs
+                .saturating_mul(2_i64.saturating_pow(exponent))
+                .min(self.max_delay_secs)
+        };
+
+        let remaining = delay - failures.secs_since_last;
+        if remaining > 0 {
+            Some(remaining)
+        } else {
+            None
+        }
+    }
+}
+
+impl LoginThrottle {
+    pub fn retry_after(&self, by_username: &FailedLogins, by_ip: &FailedLogins) -> Option<i64> {
+        let username_wait = self.per_username.retry_after(by_username);
+        let ip_wait = self.per_ip.retry_after(by_ip);
+
+        username_wait.max(ip_wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failures(count: i64, secs_since_last: i64) -> FailedLogins {
+        FailedLogins {
+            count,
+            secs_since_last,
+        }
+    }
+
+    #[test]
+    fn delay_doubles_with_every_failure() {
+        let policy = LoginThrottle::default().per_username;
+
+        assert_eq!(policy.retry_after(&failures(0, 0)), None);
+        assert_eq!(policy.retry_after(&failures(1, 0)), Some(1));
+        assert_eq!(policy.retry_after(&failures(2, 0)), Some(2));
+        assert_eq!(policy.retry_after(&failures(4, 0)), Some(8));
+        assert_eq!(policy.retry_after(&failures(4, 3)), Some(5));
+        assert_eq!(policy.retry_after(&failures(4, 8)), None);
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        let policy = ThrottlePolicy {
+            base_delay_secs: 1,
+            max_delay_secs: 60,
+            lockout_threshold: 100,
+            lockout_secs: 900,
+        };
+
+        assert_eq!(policy.retry_after(&failures(7, 0)), Some(60));
+        assert_eq!(policy.retry_after(&failures(99, 0)), Some(60));
+    }
+
+    #[test]
+    fn account_is_locked_after_threshold() {
+        let throttle = LoginThrottle::default();
+
+        assert_eq!(
+            throttle.retry_after(&failures(5, 60), &failures(5, 60)),
+            Some(15 * 60 - 60)
+        );
+        assert_eq!(
+            throttle.retry_after(&failures(5, 15 * 60), &failures(5, 15 * 60)),
+            None
+        );
+    }
+
+    #[test]
+    fn ip_failures_throttle_every_username() {
+        let throttle = LoginThrottle::default();
+
+        assert_eq!(
+            throttle.retry_after(&failures(0, 0), &failures(20, 10)),
+            Some(15 * 60 - 10)
+        );
+    }
+}
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_ezy_course_c6_search;
+DROP TABLE IF EXISTS ezy_course_c6;
+DROP TABLE IF EXISTS ezy_tutor_c6;
@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS ezy_tutor_c6 (
+    tutor_id SERIAL PRIMARY KEY,
+    tutor_name VARCHAR(200) NOT NULL,
+    tutor_pic_url VARCHAR(200) NOT NULL,
+    tutor_profile VARCHAR(200) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ezy_course_c6 (
+    course_id SERIAL PRIMARY KEY,
+    tutor_id INT NOT NULL,
+    course_name VARCHAR(140) NOT NULL,
+    course_description VARCHAR(2000),
+    course_format VARCHAR(30),
+    course_structure VARCHAR(200),
+    course_duration VARCHAR(30),
+    course_price INT,
+    course_lenguage VARCHAR(30),
+    course_level VARCHAR(30),
+    course_capacity INT,
+    posted_time TIMESTAMP DEFAULT NOW(),
+    CONSTRAINT fk_tutor
+    FOREIGN KEY(tutor_id)
+    REFERENCES ezy_tutor_c6(tutor_id)
+);
+
+/* databases created with the old dbscripts may predate course_capacity */
+ALTER TABLE ezy_course_c6 ADD COLUMN IF NOT EXISTS course_capacity INT;
+
+/* full-text index used by /courses/search, keep in sync with COURSE_SEARCH_DOCUMENT */
+CREATE INDEX IF NOT EXISTS idx_ezy_course_c6_search ON ezy_course_c6 USING GIN (
+    (setweight(to_tsvector('english', course_name), 'A') || setweight(to_tsvector('english', coalesce(course_description, '')), 'B'))
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS ezy_enrollment_c6;
+DROP TABLE IF EXISTS ezy_student_c6;
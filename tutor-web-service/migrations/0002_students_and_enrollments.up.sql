@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS ezy_student_c6 (
+    student_id SERIAL PRIMARY KEY,
+    student_name VARCHAR(200) NOT NULL,
+    student_email VARCHAR(200) NOT NULL UNIQUE
+);
+
+/* enrollments go away together with their course */
+CREATE TABLE IF NOT EXISTS ezy_enrollment_c6 (
+    student_id INT NOT NULL,
+    course_id INT NOT NULL,
+    enrolled_time TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (student_id, course_id),
+    CONSTRAINT fk_student
+    FOREIGN KEY(student_id)
+    REFERENCES ezy_student_c6(student_id),
+    CONSTRAINT fk_course
+    FOREIGN KEY(course_id)
+    REFERENCES ezy_course_c6(course_id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ezy_enrollment_c6_course ON ezy_enrollment_c6 (course_id);
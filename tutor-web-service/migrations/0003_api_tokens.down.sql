@@ -0,0 +1 @@
+DROP TABLE IF EXISTS ezy_api_token_c6;
@@ -0,0 +1,14 @@
+/* only the sha256 hex digest of each API token is stored */
+CREATE TABLE IF NOT EXISTS ezy_api_token_c6 (
+    token_hash CHAR(64) PRIMARY KEY,
+    token_role VARCHAR(10) NOT NULL CHECK (token_role IN ('admin', 'tutor', 'student')),
+    tutor_id INT REFERENCES ezy_tutor_c6(tutor_id) ON DELETE CASCADE,
+    student_id INT REFERENCES ezy_student_c6(student_id) ON DELETE CASCADE,
+    created_time TIMESTAMP DEFAULT NOW(),
+    CHECK ((token_role = 'tutor') = (tutor_id IS NOT NULL)),
+    CHECK ((token_role = 'student') = (student_id IS NOT NULL))
+);
+
+/* bootstrap an admin token, further tokens are issued through POST /auth/tokens */
+/* INSERT INTO ezy_api_token_c6(token_hash, token_role)
+VALUES (encode(sha256('change-me-admin-token'::bytea), 'hex'), 'admin'); */
@@ -0,0 +1,36 @@
+ALTER TABLE ezy_course_c6
+    DROP CONSTRAINT course_name_not_blank,
+    DROP CONSTRAINT course_duration_positive,
+    DROP CONSTRAINT course_price_not_negative,
+    DROP CONSTRAINT course_currency_iso,
+    DROP CONSTRAINT course_capacity_not_negative;
+
+ALTER TABLE ezy_course_c6 ALTER COLUMN course_level TYPE VARCHAR(30) USING initcap(course_level::text);
+ALTER TABLE ezy_course_c6 ALTER COLUMN course_format TYPE VARCHAR(30) USING course_format::text;
+
+ALTER TABLE ezy_course_c6 ADD COLUMN course_duration VARCHAR(30);
+UPDATE ezy_course_c6 SET course_duration = course_duration_minutes || ' minutes';
+ALTER TABLE ezy_course_c6 DROP COLUMN course_duration_minutes;
+
+ALTER TABLE ezy_course_c6 ADD COLUMN course_price INT;
+UPDATE ezy_course_c6 SET course_price = course_price_cents / 100;
+ALTER TABLE ezy_course_c6 DROP COLUMN course_price_cents;
+ALTER TABLE ezy_course_c6 DROP COLUMN course_currency;
+
+/* put back what could not be converted */
+UPDATE ezy_course_c6 c SET course_level = l.legacy_value
+    FROM ezy_course_c6_legacy_value l
+    WHERE l.course_id = c.course_id AND l.column_name = 'course_level';
+UPDATE ezy_course_c6 c SET course_format = l.legacy_value
+    FROM ezy_course_c6_legacy_value l
+    WHERE l.course_id = c.course_id AND l.column_name = 'course_format';
+UPDATE ezy_course_c6 c SET course_duration = l.legacy_value
+    FROM ezy_course_c6_legacy_value l
+    WHERE l.course_id = c.course_id AND l.column_name = 'course_duration';
+UPDATE ezy_course_c6 c SET course_price = l.legacy_value::int
+    FROM ezy_course_c6_legacy_value l
+    WHERE l.course_id = c.course_id AND l.column_name = 'course_price';
+
+DROP TABLE ezy_course_c6_legacy_value;
+DROP TYPE course_format;
+DROP TYPE course_level;
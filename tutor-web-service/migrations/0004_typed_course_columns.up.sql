@@ -0,0 +1,89 @@
+/* course level and format become enums, the duration a number of minutes and
+   the price an amount in cents plus a currency */
+CREATE TYPE course_level AS ENUM ('beginner', 'intermediate', 'advanced');
+CREATE TYPE course_format AS ENUM ('ebook', 'video', 'audio', 'live', 'in_person');
+
+/* free-form values that could not be converted are kept here, so they can be
+   fixed by hand and nothing entered by tutors is lost */
+CREATE TABLE IF NOT EXISTS ezy_course_c6_legacy_value (
+    course_id INT NOT NULL REFERENCES ezy_course_c6(course_id) ON DELETE CASCADE,
+    column_name VARCHAR(30) NOT NULL,
+    legacy_value VARCHAR(2000) NOT NULL,
+    PRIMARY KEY (course_id, column_name)
+);
+
+/* earlier versions stored '' for unset fields */
+UPDATE ezy_course_c6 SET course_level = NULL WHERE trim(course_level) = '';
+UPDATE ezy_course_c6 SET course_format = NULL WHERE trim(course_format) = '';
+UPDATE ezy_course_c6 SET course_duration = NULL WHERE trim(course_duration) = '';
+UPDATE ezy_course_c6 SET course_lenguage = NULL WHERE trim(course_lenguage) = '';
+UPDATE ezy_course_c6 SET course_structure = NULL WHERE trim(course_structure) = '';
+
+/* level */
+ALTER TABLE ezy_course_c6 ADD COLUMN course_level_enum course_level;
+UPDATE ezy_course_c6 SET course_level_enum = CASE lower(trim(course_level))
+    WHEN 'beginner' THEN 'beginner'
+    WHEN 'basic' THEN 'beginner'
+    WHEN 'intermediate' THEN 'intermediate'
+    WHEN 'advanced' THEN 'advanced'
+    WHEN 'expert' THEN 'advanced'
+    END::course_level;
+INSERT INTO ezy_course_c6_legacy_value (course_id, column_name, legacy_value)
+    SELECT course_id, 'course_level', course_level FROM ezy_course_c6
+    WHERE course_level IS NOT NULL AND course_level_enum IS NULL;
+ALTER TABLE ezy_course_c6 DROP COLUMN course_level;
+ALTER TABLE ezy_course_c6 RENAME COLUMN course_level_enum TO course_level;
+
+/* format */
+ALTER TABLE ezy_course_c6 ADD COLUMN course_format_enum course_format;
+UPDATE ezy_course_c6 SET course_format_enum = CASE regexp_replace(lower(trim(course_format)), '[^a-z]', '', 'g')
+    WHEN 'ebook' THEN 'ebook'
+    WHEN 'book' THEN 'ebook'
+    WHEN 'video' THEN 'video'
+    WHEN 'audio' THEN 'audio'
+    WHEN 'podcast' THEN 'audio'
+    WHEN 'live' THEN 'live'
+    WHEN 'webinar' THEN 'live'
+    WHEN 'inperson' THEN 'in_person'
+    WHEN 'classroom' THEN 'in_person'
+    END::course_format;
+INSERT INTO ezy_course_c6_legacy_value (course_id, column_name, legacy_value)
+    SELECT course_id, 'course_format', course_format FROM ezy_course_c6
+    WHERE course_format IS NOT NULL AND course_format_enum IS NULL;
+ALTER TABLE ezy_course_c6 DROP COLUMN course_format;
+ALTER TABLE ezy_course_c6 RENAME COLUMN course_format_enum TO course_format;
+
+/* duration, e.g. '90', '45 min', '2 hours', '1.5h' or '3 weeks' */
+ALTER TABLE ezy_course_c6 ADD COLUMN course_duration_minutes INT;
+UPDATE ezy_course_c6 SET course_duration_minutes = round(
+    substring(course_duration FROM '[0-9]+(?:\.[0-9]+)?')::numeric *
+    CASE
+        WHEN course_duration ~* '^\s*[0-9]+(\.[0-9]+)?\s*(m|min|mins|minute|minutes)?\s*$' THEN 1
+        WHEN course_duration ~* '^\s*[0-9]+(\.[0-9]+)?\s*(h|hr|hrs|hour|hours)\s*$' THEN 60
+        WHEN course_duration ~* '^\s*[0-9]+(\.[0-9]+)?\s*(d|day|days)\s*$' THEN 60 * 24
+        WHEN course_duration ~* '^\s*[0-9]+(\.[0-9]+)?\s*(w|wk|wks|week|weeks)\s*$' THEN 60 * 24 * 7
+    END)
+    WHERE course_duration ~ '[0-9]';
+UPDATE ezy_course_c6 SET course_duration_minutes = NULL WHERE course_duration_minutes <= 0;
+INSERT INTO ezy_course_c6_legacy_value (course_id, column_name, legacy_value)
+    SELECT course_id, 'course_duration', course_duration FROM ezy_course_c6
+    WHERE course_duration IS NOT NULL AND course_duration_minutes IS NULL;
+ALTER TABLE ezy_course_c6 DROP COLUMN course_duration;
+
+/* price, whole currency units so far */
+ALTER TABLE ezy_course_c6 ADD COLUMN course_price_cents INT;
+UPDATE ezy_course_c6 SET course_price_cents = course_price * 100 WHERE course_price >= 0;
+INSERT INTO ezy_course_c6_legacy_value (course_id, column_name, legacy_value)
+    SELECT course_id, 'course_price', course_price::text FROM ezy_course_c6
+    WHERE course_price < 0;
+ALTER TABLE ezy_course_c6 DROP COLUMN course_price;
+ALTER TABLE ezy_course_c6 ADD COLUMN course_currency CHAR(3) NOT NULL DEFAULT 'USD';
+
+UPDATE ezy_course_c6 SET course_capacity = NULL WHERE course_capacity < 0;
+
+ALTER TABLE ezy_course_c6
+    ADD CONSTRAINT course_name_not_blank CHECK (trim(course_name) <> ''),
+    ADD CONSTRAINT course_duration_positive CHECK (course_duration_minutes > 0),
+    ADD CONSTRAINT course_price_not_negative CHECK (course_price_cents >= 0),
+    ADD CONSTRAINT course_currency_iso CHECK (course_currency ~ '^[A-Z]{3}$'),
+    ADD CONSTRAINT course_capacity_not_negative CHECK (course_capacity >= 0);
@@ -0,0 +1,3 @@
+ALTER TABLE ezy_course_c6
+    DROP CONSTRAINT course_currency_iso,
+    ADD CONSTRAINT course_currency_iso CHECK (course_currency ~ '^[A-Z]{3}$');
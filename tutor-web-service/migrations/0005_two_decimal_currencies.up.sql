@@ -0,0 +1,13 @@
+/* prices are kept in cents, so only currencies whose minor unit is a
+   hundredth can be used. ISO 4217 currencies with 0, 3 or 4 decimals are
+   refused, e.g. JPY and KRW or BHD and KWD. */
+ALTER TABLE ezy_course_c6
+    DROP CONSTRAINT course_currency_iso,
+    ADD CONSTRAINT course_currency_iso CHECK (
+        course_currency ~ '^[A-Z]{3}$' AND course_currency NOT IN (
+            'BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG',
+            'RWF', 'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF',
+            'BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND',
+            'CLF', 'UYW'
+        )
+    );
@@ -1,10 +1,14 @@
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware, web, App, HttpServer};
 use dotenv::dotenv;
 use errors::EzyTutorError;
-use sqlx::postgres::PgPool;
 use std::env;
 use std::io;
-use std::sync::Mutex;
+use std::process;
+use tutor_common::metrics::track_requests;
+use tutor_common::migrate::{run_migrate_command, run_migrations};
+use tutor_common::request_id::request_id;
+use tutor_common::shutdown::stop_on_signal;
+use tutor_common::telemetry::init_tracing;
 
 #[path = "../iter5/handlers/mod.rs"]
 mod handlers;
@@ -21,8 +25,16 @@ mod state;
 #[path = "../iter5/dbaccess/mod.rs"]
 mod dbaccess;
 
-#[path = "../iter5/errors.rs"]
-mod errors;
+use tutor_common::errors;
+
+#[path = "../iter5/auth.rs"]
+mod auth;
+
+#[path = "../iter5/migrate.rs"]
+mod migrate;
+
+#[path = "../iter5/settings.rs"]
+mod settings;
 
 use routes::*;
 use state::AppState;
@@ -30,29 +42,70 @@ use state::AppState;
 #[actix_rt::main]
 async fn main() -> io::Result<()> {
     dotenv().ok();
+    let settings = settings::load().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    init_tracing(&settings.log);
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+    let db_pool = settings
+        .database
+        .connect()
+        .await
+        .map_err(|err| io::Error::other(format!("Cannot connect to the database: {}", err)))?;
 
-    let db_pool = PgPool::connect(&database_url).await.unwrap();
+    // `iter5 migrate ...` manages the schema and exits, RUN_MIGRATIONS=true
+    // applies pending migrations before serving
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return run_migrate_command(&db_pool, &migrate::migrator(), "iter5", &args[1..]).await;
+    }
+    if settings.database.run_migrations {
+        run_migrations(&db_pool, &migrate::migrator()).await?;
+    }
 
     //Construct Appstate
     let shared_data = web::Data::new(AppState {
-        health_check_response: "I'm good, You've already asked me ".to_string(),
-        visit_count: Mutex::new(0),
-        db: db_pool,
+        db: db_pool.clone(),
     });
 
+    let cors = settings.cors.clone();
     let app = move || {
         App::new()
+            .wrap(cors.middleware())
+            .wrap(middleware::from_fn(track_requests))
+            .wrap(middleware::from_fn(request_id))
             .app_data(shared_data.clone())
             .app_data(web::JsonConfig::default().error_handler(|_err, _req| {
                 EzyTutorError::InvalidInput("Please provide valid Json input".to_string()).into()
             }))
+            .app_data(web::QueryConfig::default().error_handler(|err, _req| {
+                EzyTutorError::InvalidInput(format!("Invalid query parameters: {}", err)).into()
+            }))
             .configure(general_routes)
             .configure(course_routes)
             .configure(tutor_routes)
+            .configure(student_routes)
+            .configure(auth_routes)
+    };
+
+    let mut server = HttpServer::new(app)
+        .shutdown_timeout(settings.server.shutdown_timeout_secs)
+        .disable_signals();
+    if let Some(workers) = settings.server.workers {
+        server = server.workers(workers);
+    }
+    let server = match &settings.server.tls {
+        Some(tls) => server.bind_openssl(&settings.server.bind, tls.acceptor()?)?,
+        None => server.bind(&settings.server.bind)?,
     };
+    tracing::info!("Listening on {}", settings.server.bind);
+
+    let server = server.run();
+    actix_rt::spawn(stop_on_signal(server.handle()));
+    server.await?;
 
-    let host_port = env::var("HOST_PORT").expect("HOTS:PORT address is not set in .env file");
-    HttpServer::new(app).bind(&host_port)?.run().await
+    db_pool.close().await;
+    tracing::info!("Shut down");
+    Ok(())
 }
@@ -1,8 +1,6 @@
 use super::models::Course;
 use super::state::AppState;
-use actix_web::http::StatusCode;
 use actix_web::{web, HttpResponse};
-use sqlx::postgres::PgPool;
 
 pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpResponse {
     let health_check_response = &app_state.health_check_response;
@@ -14,22 +12,22 @@ pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpRespons
 }
 
 pub async fn get_courses_for_tutor(
-    app_state: web::Data<AppState>,
-    params: web::Path<(usize,)>,
+    _app_state: web::Data<AppState>,
+    _params: web::Path<(usize,)>,
 ) -> HttpResponse {
     HttpResponse::Ok().json("Success")
 }
 
 pub async fn get_course_details(
-    app_state: web::Data<AppState>,
-    params: web::Path<(usize, usize)>,
+    _app_state: web::Data<AppState>,
+    _params: web::Path<(usize, usize)>,
 ) -> HttpResponse {
     HttpResponse::Ok().json("Success")
 }
 
 pub async fn post_new_course(
-    app_state: web::Data<AppState>,
-    new_course: web::Json<Course>,
+    _app_state: web::Data<AppState>,
+    _new_course: web::Json<Course>,
 ) -> HttpResponse {
     HttpResponse::Ok().json("Success")
 }
@@ -99,7 +97,7 @@ mod test {
             course_id: 1,
             tutor_id: 1,
             course_name: "This is the next course".into(),
-            posted_time: Some(NaiveDate::from_ymd(2022, 5, 6).and_hms(14, 49, 30)),
+            posted_time: Some(NaiveDate::from_ymd_opt(2022, 5, 6).unwrap().and_hms_opt(14, 49, 30).unwrap()),
         };
 
         let res = post_new_course(app_state, web::Json(new_course_msg)).await;
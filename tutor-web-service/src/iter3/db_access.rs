@@ -16,7 +16,7 @@ pub async fn get_courses_for_tutor_db(pool: &PgPool, tutor_id: i32) -> Vec<Cours
             course_id: course_row.course_id,
             tutor_id: course_row.tutor_id,
             course_name: course_row.course_name.clone(),
-            posted_time: Some(chrono::NaiveDateTime::from(course_row.posted_time.unwrap())),
+            posted_time: Some(course_row.posted_time.unwrap()),
         })
         .collect()
 }
@@ -35,7 +35,7 @@ pub async fn get_course_details_db(pool: &PgPool, tutor_id: i32, course_id: i32)
         course_id: course_row.course_id,
         tutor_id: course_row.tutor_id,
         course_name: course_row.course_name.clone(),
-        posted_time: Some(chrono::NaiveDateTime::from(course_row.posted_time.unwrap())),
+        posted_time: Some(course_row.posted_time.unwrap()),
     }
 }
 
@@ -54,6 +54,6 @@ pub async fn post_new_course_db(pool: &PgPool, new_course: Course) -> Course {
         course_id: course_row.course_id,
         tutor_id: course_row.tutor_id,
         course_name: course_row.course_name.clone(),
-        posted_time: Some(chrono::NaiveDateTime::from(course_row.posted_time.unwrap())),
+        posted_time: Some(course_row.posted_time.unwrap()),
     }
 }
@@ -108,7 +108,7 @@ mod test {
             course_id: 3,
             tutor_id: 1,
             course_name: "This is the next course".into(),
-            posted_time: Some(NaiveDate::from_ymd(2022, 5, 6).and_hms(14, 49, 30)),
+            posted_time: Some(NaiveDate::from_ymd_opt(2022, 5, 6).unwrap().and_hms_opt(14, 49, 30).unwrap()),
         };
 
         let res = post_new_course(app_state, web::Json(new_course_msg)).await;
@@ -0,0 +1,157 @@
+use crate::dbaccess::auth::get_identity_for_token_db;
+use crate::errors::EzyTutorError;
+use crate::models::auth::{Identity, Role};
+use crate::state::AppState;
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::pin::Pin;
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+const TOKEN_LENGTH: usize = 40;
+
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+// Tokens are accepted either as `Authorization: Bearer <token>` or as
+// `X-API-Key: <token>`.
+fn token_from_request(req: &HttpRequest) -> Option<String> {
+    let token = match req.headers().get(header::AUTHORIZATION) {
+        Some(value) => value.to_str().ok()?.strip_prefix("Bearer ")?,
+        None => req.headers().get(API_KEY_HEADER)?.to_str().ok()?,
+    };
+
+    Some(token.trim().to_string()).filter(|token| !token.is_empty())
+}
+
+impl FromRequest for Identity {
+    type Error = EzyTutorError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = token_from_request(req);
+        let app_state = req.app_data::<web::Data<AppState>>().cloned();
+
+        Box::pin(async move {
+            let token =
+                token.ok_or_else(|| EzyTutorError::Unauthorized("Missing API token".into()))?;
+            let app_state = app_state.ok_or_else(|| {
+                EzyTutorError::ActixError("Application state is not configured".into())
+            })?;
+
+            get_identity_for_token_db(&app_state.db, &hash_token(&token)).await
+        })
+    }
+}
+
+impl Identity {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    pub fn require_admin(&self) -> Result<(), EzyTutorError> {
+        if self.is_admin() {
+            Ok(())
+        } else {
+            Err(EzyTutorError::Forbidden(
+                "Only admins may perform this action".into(),
+            ))
+        }
+    }
+
+    // Admins may act on behalf of any tutor, tutors only on their own behalf
+    pub fn require_tutor(&self, tutor_id: i32) -> Result<(), EzyTutorError> {
+        if self.is_admin() || (self.role == Role::Tutor && self.tutor_id == Some(tutor_id)) {
+            Ok(())
+        } else {
+            Err(EzyTutorError::Forbidden(format!(
+                "Not allowed to act on behalf of tutor {}",
+                tutor_id
+            )))
+        }
+    }
+
+    // Admins may act on behalf of any student, students only on their own behalf
+    pub fn require_student(&self, student_id: i32) -> Result<(), EzyTutorError> {
+        if self.is_admin() || (self.role == Role::Student && self.student_id == Some(student_id)) {
+            Ok(())
+        } else {
+            Err(EzyTutorError::Forbidden(format!(
+                "Not allowed to act on behalf of student {}",
+                student_id
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use actix_web::ResponseError;
+
+    #[test]
+    fn tutor_may_only_act_for_self() {
+        assert!(Identity::tutor(1).require_tutor(1).is_ok());
+        assert_eq!(
+            Identity::tutor(1)
+                .require_tutor(2)
+                .unwrap_err()
+                .status_code(),
+            StatusCode::FORBIDDEN
+        );
+        assert!(Identity::tutor(1).require_student(1).is_err());
+        assert!(Identity::tutor(1).require_admin().is_err());
+    }
+
+    #[test]
+    fn student_may_only_act_for_self() {
+        assert!(Identity::student(3).require_student(3).is_ok());
+        assert!(Identity::student(3).require_student(4).is_err());
+        assert!(Identity::student(3).require_tutor(3).is_err());
+    }
+
+    #[test]
+    fn admin_may_act_for_anyone() {
+        assert!(Identity::admin().require_admin().is_ok());
+        assert!(Identity::admin().require_tutor(1).is_ok());
+        assert!(Identity::admin().require_student(1).is_ok());
+    }
+
+    #[test]
+    fn token_is_read_from_either_header() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer abc"))
+            .to_http_request();
+        assert_eq!(token_from_request(&req), Some("abc".to_string()));
+
+        let req = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "abc"))
+            .to_http_request();
+        assert_eq!(token_from_request(&req), Some("abc".to_string()));
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Basic abc"))
+            .to_http_request();
+        assert_eq!(token_from_request(&req), None);
+    }
+
+    #[actix_rt::test]
+    async fn missing_token_is_unauthorized() {
+        let req = TestRequest::default().to_http_request();
+        let res = Identity::extract(&req).await;
+
+        assert_eq!(res.unwrap_err().status_code(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::errors::EzyTutorError;
+use crate::models::auth::{ApiToken, Identity, NewApiToken, Role};
+use sqlx::postgres::PgPool;
+use tracing::instrument;
+
+#[instrument(skip_all)]
+pub async fn get_identity_for_token_db(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<Identity, EzyTutorError> {
+    let token_row = sqlx::query!(
+        "SELECT token_role, tutor_id, student_id FROM ezy_api_token_c6 WHERE token_hash = $1",
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| EzyTutorError::Unauthorized("Invalid API token".into()))?;
+
+    let role = token_row
+        .token_role
+        .parse::<Role>()
+        .map_err(EzyTutorError::DBError)?;
+
+    Ok(Identity {
+        role,
+        tutor_id: token_row.tutor_id,
+        student_id: token_row.student_id,
+    })
+}
+
+#[instrument(skip_all)]
+pub async fn post_new_token_db(
+    pool: &PgPool,
+    token: String,
+    token_hash: &str,
+    new_token: NewApiToken,
+) -> Result<ApiToken, EzyTutorError> {
+    let token_row = sqlx::query!(
+        "INSERT INTO ezy_api_token_c6 (token_hash, token_role, tutor_id, student_id) VALUES ($1, $2, $3, $4) returning tutor_id, student_id",
+        token_hash,
+        new_token.role.to_string(),
+        new_token.tutor_id,
+        new_token.student_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(ApiToken {
+        token,
+        role: new_token.role,
+        tutor_id: token_row.tutor_id,
+        student_id: token_row.student_id,
+    })
+}
@@ -1,22 +1,190 @@
 use crate::errors::EzyTutorError;
-use crate::{handlers::course::*, models::course::*};
-use sqlx::{postgres::PgPool, query, query_as};
+use crate::models::course::*;
+use crate::models::paging::{Page, SortOrder};
+use sqlx::{postgres::PgPool, query, query_as, query_scalar};
+use tracing::instrument;
 
+// Filters shared by the course listing and its total count. Unset filters are
+// bound as NULL and match every row.
+const COURSE_LIST_FILTERS: &str = "WHERE tutor_id = $1
+    AND ($2::course_level IS NULL OR course_level = $2)
+    AND ($3::course_format IS NULL OR course_format = $3)
+    AND ($4::varchar IS NULL OR course_lenguage = $4)
+    AND ($5::int IS NULL OR course_price_cents >= $5)
+    AND ($6::int IS NULL OR course_price_cents <= $6)";
+
+#[instrument(skip(pool, course_query))]
 pub async fn get_courses_for_tutor_db(
     pool: &PgPool,
     tutor_id: i32,
-) -> Result<Vec<Course>, EzyTutorError> {
-    let course_rows = query_as!(
-        Course,
-        "SELECT * FROM ezy_course_c6 WHERE tutor_id = $1",
-        tutor_id
+    course_query: &CourseQuery,
+) -> Result<Page<Course>, EzyTutorError> {
+    let page = course_query.page();
+    let limit = page.limit()?;
+    let offset = page.offset()?;
+
+    if let (Some(min_price), Some(max_price)) =
+        (course_query.min_price_cents, course_query.max_price_cents)
+    {
+        if min_price > max_price {
+            return Err(EzyTutorError::InvalidInput(
+                "min_price_cents must not be greater than max_price_cents".into(),
+            ));
+        }
+    }
+
+    let sort_by = course_query.sort_by.unwrap_or(CourseSortField::PostedTime);
+    let order = course_query.order.unwrap_or(SortOrder::Desc);
+
+    let total: i64 = query_scalar(&format!(
+        "SELECT COUNT(*) FROM ezy_course_c6 {}",
+        COURSE_LIST_FILTERS
+    ))
+    .bind(tutor_id)
+    .bind(course_query.course_level)
+    .bind(course_query.course_format)
+    .bind(&course_query.course_lenguage)
+    .bind(course_query.min_price_cents)
+    .bind(course_query.max_price_cents)
+    .fetch_one(pool)
+    .await?;
+
+    let course_rows = query_as::<_, Course>(&format!(
+        "SELECT * FROM ezy_course_c6 {} ORDER BY {} {} NULLS LAST, course_id {} LIMIT $7 OFFSET $8",
+        COURSE_LIST_FILTERS,
+        sort_by.as_sql(),
+        order.as_sql(),
+        order.as_sql()
+    ))
+    .bind(tutor_id)
+    .bind(course_query.course_level)
+    .bind(course_query.course_format)
+    .bind(&course_query.course_lenguage)
+    .bind(course_query.min_price_cents)
+    .bind(course_query.max_price_cents)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Page::new(course_rows, total, limit, offset))
+}
+
+// Must stay identical to the expression behind `idx_ezy_course_c6_search` so
+// that Postgres can use the index.
+const COURSE_SEARCH_DOCUMENT: &str = "(setweight(to_tsvector('english', course_name), 'A') || setweight(to_tsvector('english', coalesce(course_description, '')), 'B'))";
+
+// Course text with the HTML special characters escaped, so that the only
+// markup in a snippet is the <mark> added around matching words
+const COURSE_SEARCH_TEXT: &str = r#"replace(replace(replace(replace(replace(
+    course_name || ' ' || coalesce(course_description, ''),
+    '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;')"#;
+
+// Facet filters ($2 - $4), kept apart so that each facet can be counted
+// without its own filter
+const COURSE_LEVEL_FILTER: &str = "($2::course_level IS NULL OR course_level = $2)";
+const COURSE_FORMAT_FILTER: &str = "($3::course_format IS NULL OR course_format = $3)";
+const COURSE_LENGUAGE_FILTER: &str = "($4::varchar IS NULL OR course_lenguage = $4)";
+
+// Selects `columns` of the courses matching the search terms ($1), which
+// are available as `query`. Without search terms every course matches.
+fn course_search_matches(columns: &str) -> String {
+    format!(
+        "SELECT {columns}
+        FROM ezy_course_c6, websearch_to_tsquery('english', coalesce($1::text, '')) query
+        WHERE ($1::text IS NULL OR {document} @@ query)",
+        columns = columns,
+        document = COURSE_SEARCH_DOCUMENT
     )
+}
+
+#[instrument(skip_all)]
+pub async fn search_courses_db(
+    pool: &PgPool,
+    search_query: &CourseSearchQuery,
+) -> Result<CourseSearchResults, EzyTutorError> {
+    let page = search_query.page();
+    let limit = page.limit()?;
+    let offset = page.offset()?;
+    let terms = search_query.terms();
+
+    // The counts of a facet apply every filter but its own, so that the
+    // other values of a filtered facet are still offered
+    let facet_rows: Vec<(String, Option<String>, i64)> = query_as(&format!(
+        "WITH matches AS ({matches})
+        SELECT 'total', NULL, COUNT(*) FROM matches WHERE {level} AND {format} AND {lenguage}
+        UNION ALL SELECT 'course_level', course_level::text, COUNT(*) FROM matches
+            WHERE {format} AND {lenguage} GROUP BY course_level
+        UNION ALL SELECT 'course_format', course_format::text, COUNT(*) FROM matches
+            WHERE {level} AND {lenguage} GROUP BY course_format
+        UNION ALL SELECT 'course_lenguage', course_lenguage, COUNT(*) FROM matches
+            WHERE {level} AND {format} GROUP BY course_lenguage",
+        matches = course_search_matches("course_level, course_format, course_lenguage"),
+        level = COURSE_LEVEL_FILTER,
+        format = COURSE_FORMAT_FILTER,
+        lenguage = COURSE_LENGUAGE_FILTER
+    ))
+    .bind(terms)
+    .bind(search_query.course_level)
+    .bind(search_query.course_format)
+    .bind(&search_query.course_lenguage)
+    .fetch_all(pool)
+    .await?;
+
+    let mut total = 0;
+    let mut facets = CourseFacets::default();
+    for (facet, value, count) in facet_rows {
+        let facet_count = FacetCount { value, count };
+        match facet.as_str() {
+            "total" => total = count,
+            "course_level" => facets.course_level.push(facet_count),
+            "course_format" => facets.course_format.push(facet_count),
+            _ => facets.course_lenguage.push(facet_count),
+        }
+    }
+    for facet_counts in [
+        &mut facets.course_level,
+        &mut facets.course_format,
+        &mut facets.course_lenguage,
+    ] {
+        facet_counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    }
+
+    // Snippets are the most expensive part of the search, so they are only
+    // made for the courses of the page
+    let ranked_columns = format!(
+        "ezy_course_c6.*, query,
+        CASE WHEN $1::text IS NULL THEN 0 ELSE ts_rank({}, query) END AS rank",
+        COURSE_SEARCH_DOCUMENT
+    );
+    let course_rows = query_as::<_, CourseSearchHit>(&format!(
+        "SELECT hits.*, ts_headline('english', {text}, query,
+            'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=25, MinWords=5') AS snippet
+        FROM ({matches} AND {level} AND {format} AND {lenguage}
+            ORDER BY rank DESC, posted_time DESC NULLS LAST, course_id DESC LIMIT $5 OFFSET $6) hits
+        ORDER BY rank DESC, posted_time DESC NULLS LAST, course_id DESC",
+        text = COURSE_SEARCH_TEXT,
+        matches = course_search_matches(&ranked_columns),
+        level = COURSE_LEVEL_FILTER,
+        format = COURSE_FORMAT_FILTER,
+        lenguage = COURSE_LENGUAGE_FILTER
+    ))
+    .bind(terms)
+    .bind(search_query.course_level)
+    .bind(search_query.course_format)
+    .bind(&search_query.course_lenguage)
+    .bind(limit)
+    .bind(offset)
     .fetch_all(pool)
     .await?;
 
-    Ok(course_rows)
+    Ok(CourseSearchResults {
+        page: Page::new(course_rows, total, limit, offset),
+        facets,
+    })
 }
 
+#[instrument(skip(pool))]
 pub async fn get_course_details_db(
     pool: &PgPool,
     tutor_id: i32,
@@ -24,7 +192,12 @@ pub async fn get_course_details_db(
 ) -> Result<Course, EzyTutorError> {
     let course_row = query_as!(
         Course,
-        "SELECT * FROM ezy_course_c6 WHERE tutor_id = $1 and course_id = $2",
+        r#"SELECT course_id, tutor_id, course_name, course_description,
+            course_format as "course_format: CourseFormat", course_structure,
+            course_duration_minutes, course_price_cents, course_currency,
+            course_lenguage, course_level as "course_level: CourseLevel",
+            course_capacity, posted_time
+        FROM ezy_course_c6 WHERE tutor_id = $1 and course_id = $2"#,
         tutor_id,
         course_id
     )
@@ -38,22 +211,33 @@ pub async fn get_course_details_db(
     }
 }
 
+#[instrument(skip_all, fields(tutor_id = new_course.tutor_id))]
 pub async fn post_new_course_db(
     pool: &PgPool,
     new_course: CreateCourse,
 ) -> Result<Course, EzyTutorError> {
     let course_row = query_as!(
         Course,
-        "INSERT INTO ezy_course_c6 (tutor_id, course_name, course_description, course_duration, course_level, course_format, course_lenguage, course_structure, course_price) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) returning tutor_id, course_id, course_name, course_description, course_duration, course_level, course_format, course_lenguage, course_structure, course_price, posted_time",
+        r#"INSERT INTO ezy_course_c6 (tutor_id, course_name, course_description, course_duration_minutes, course_level, course_format, course_lenguage, course_structure, course_price_cents, course_currency, course_capacity)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        returning course_id, tutor_id, course_name, course_description,
+        course_format as "course_format: CourseFormat", course_structure,
+        course_duration_minutes, course_price_cents, course_currency,
+        course_lenguage, course_level as "course_level: CourseLevel",
+        course_capacity, posted_time"#,
         new_course.tutor_id,
         new_course.course_name,
         new_course.course_description,
-        new_course.course_duration,
-        new_course.course_level,
-        new_course.course_format,
+        new_course.course_duration_minutes,
+        new_course.course_level as Option<CourseLevel>,
+        new_course.course_format as Option<CourseFormat>,
         new_course.course_lenguage,
         new_course.course_structure,
-        new_course.course_price,
+        new_course.course_price_cents,
+        new_course
+            .course_currency
+            .unwrap_or_else(|| DEFAULT_CURRENCY.to_string()),
+        new_course.course_capacity,
     )
     .fetch_one(pool)
     .await?;
@@ -61,6 +245,7 @@ pub async fn post_new_course_db(
     Ok(course_row)
 }
 
+#[instrument(skip(pool))]
 pub async fn delete_course_db(
     pool: &PgPool,
     tutor_id: i32,
@@ -72,11 +257,13 @@ pub async fn delete_course_db(
         course_id
     )
     .execute(pool)
-    .await?;
+    .await
+    .map_err(EzyTutorError::from_delete)?;
 
     Ok(format!("Deleted {:?} Record", course_row))
 }
 
+#[instrument(skip(pool, update_course))]
 pub async fn update_course_details_db(
     pool: &PgPool,
     tutor_id: i32,
@@ -85,95 +272,84 @@ pub async fn update_course_details_db(
 ) -> Result<Course, EzyTutorError> {
     let current_course_row = query_as!(
         Course,
-        "SELECT * FROM ezy_course_c6 WHERE tutor_id = $1 and course_id = $2",
+        r#"SELECT course_id, tutor_id, course_name, course_description,
+            course_format as "course_format: CourseFormat", course_structure,
+            course_duration_minutes, course_price_cents, course_currency,
+            course_lenguage, course_level as "course_level: CourseLevel",
+            course_capacity, posted_time
+        FROM ezy_course_c6 WHERE tutor_id = $1 and course_id = $2"#,
         tutor_id,
         course_id
     )
-    .fetch_one(pool)
-    .await
-    .map_err(|_err| EzyTutorError::NotFound("Course id not found".into()))?;
-
-    let name: String = if let Some(name) = update_course.course_name {
-        name
-    } else {
-        current_course_row.course_name
-    };
-
-    let description: String = if let Some(desc) = update_course.course_description {
-        desc
-    } else {
-        current_course_row.course_description.unwrap_or_default()
-    };
-
-    let format: String = if let Some(format) = update_course.course_format {
-        format
-    } else {
-        current_course_row.course_format.unwrap_or_default()
-    };
-
-    let structure: String = if let Some(structure) = update_course.course_structure {
-        structure
-    } else {
-        current_course_row.course_structure.unwrap_or_default()
-    };
-
-    let duration: String = if let Some(duration) = update_course.course_duration {
-        duration
-    } else {
-        current_course_row.course_duration.unwrap_or_default()
-    };
-
-    let level: String = if let Some(level) = update_course.course_level {
-        level
-    } else {
-        current_course_row.course_level.unwrap_or_default()
-    };
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| EzyTutorError::NotFound("Course id not found".into()))?;
 
-    let lenguage: String = if let Some(lenguage) = update_course.course_lenguage {
-        lenguage
-    } else {
-        current_course_row.course_lenguage.unwrap_or_default()
-    };
-
-    let price = if let Some(price) = update_course.course_price {
-        price
-    } else {
-        current_course_row.course_price.unwrap_or_default()
-    };
+    let name = update_course
+        .course_name
+        .unwrap_or(current_course_row.course_name);
+    let description = update_course
+        .course_description
+        .or(current_course_row.course_description);
+    let format = update_course
+        .course_format
+        .or(current_course_row.course_format);
+    let structure = update_course
+        .course_structure
+        .or(current_course_row.course_structure);
+    let duration = update_course
+        .course_duration_minutes
+        .or(current_course_row.course_duration_minutes);
+    let price = update_course
+        .course_price_cents
+        .or(current_course_row.course_price_cents);
+    let currency = update_course
+        .course_currency
+        .unwrap_or(current_course_row.course_currency);
+    let lenguage = update_course
+        .course_lenguage
+        .or(current_course_row.course_lenguage);
+    let level = update_course
+        .course_level
+        .or(current_course_row.course_level);
+    let capacity = update_course
+        .course_capacity
+        .or(current_course_row.course_capacity);
 
     let course_row = query_as!(
         Course,
-        "UPDATE ezy_course_c6 set course_name = $1,
+        r#"UPDATE ezy_course_c6 set course_name = $1,
         course_description = $2,
         course_format = $3,
         course_structure = $4,
-        course_duration = $5,
-        course_price = $6,
-        course_lenguage = $7,
-        course_level = $8
+        course_duration_minutes = $5,
+        course_price_cents = $6,
+        course_currency = $7,
+        course_lenguage = $8,
+        course_level = $9,
+        course_capacity = $10
         WHERE
-        tutor_id = $9 and course_id = $10
-        returning tutor_id, course_id,
-        course_name, course_description,
-        course_duration, course_level,
-        course_format, course_lenguage, course_structure, course_price, posted_time",
+        tutor_id = $11 and course_id = $12
+        returning course_id, tutor_id, course_name, course_description,
+        course_format as "course_format: CourseFormat", course_structure,
+        course_duration_minutes, course_price_cents, course_currency,
+        course_lenguage, course_level as "course_level: CourseLevel",
+        course_capacity, posted_time"#,
         name,
         description,
-        format,
+        format as Option<CourseFormat>,
         structure,
         duration,
         price,
+        currency,
         lenguage,
-        level,
+        level as Option<CourseLevel>,
+        capacity,
         tutor_id,
         course_id
     )
     .fetch_one(pool)
-    .await;
+    .await?;
 
-    if let Ok(course) = course_row {
-        Ok(course)
-    } else {
-        Err(EzyTutorError::NotFound("course id not found".into()))
-    }
+    Ok(course_row)
 }
@@ -0,0 +1,133 @@
+use crate::errors::EzyTutorError;
+use crate::models::enrollment::{Enrollment, NewEnrollment, RosterEntry, StudentEnrollment};
+use sqlx::postgres::PgPool;
+use tracing::instrument;
+
+// Enrollments for one course are serialized by locking the course row, so the
+// capacity check and the insert can not race with a concurrent enrollment.
+#[instrument(skip(pool, new_enrollment))]
+pub async fn enroll_student_db(
+    pool: &PgPool,
+    student_id: i32,
+    new_enrollment: NewEnrollment,
+) -> Result<Enrollment, EzyTutorError> {
+    let course_id = new_enrollment.course_id;
+    let mut tx = pool.begin().await?;
+
+    let course_row = sqlx::query!(
+        "SELECT course_capacity FROM ezy_course_c6 WHERE course_id = $1 FOR UPDATE",
+        course_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or_else(|| EzyTutorError::NotFound("Course id not found".into()))?;
+
+    sqlx::query!(
+        "SELECT student_id FROM ezy_student_c6 WHERE student_id = $1",
+        student_id
+    )
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or_else(|| EzyTutorError::NotFound("Student id not found".into()))?;
+
+    let enrollment_row = sqlx::query!(
+        r#"SELECT COUNT(*) as "enrolled!",
+        COUNT(*) FILTER (WHERE student_id = $2) as "already_enrolled!"
+        FROM ezy_enrollment_c6 WHERE course_id = $1"#,
+        course_id,
+        student_id
+    )
+    .fetch_one(&mut tx)
+    .await?;
+
+    if enrollment_row.already_enrolled > 0 {
+        return Err(EzyTutorError::Conflict(format!(
+            "Student {} is already enrolled in course {}",
+            student_id, course_id
+        )));
+    }
+
+    if let Some(capacity) = course_row.course_capacity {
+        if enrollment_row.enrolled >= i64::from(capacity) {
+            return Err(EzyTutorError::Conflict(format!(
+                "Course {} is full ({} students)",
+                course_id, capacity
+            )));
+        }
+    }
+
+    let enrollment = sqlx::query_as!(
+        Enrollment,
+        "INSERT INTO ezy_enrollment_c6 (student_id, course_id) VALUES ($1, $2) returning student_id, course_id, enrolled_time",
+        student_id,
+        course_id
+    )
+    .fetch_one(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(enrollment)
+}
+
+#[instrument(skip(pool))]
+pub async fn unenroll_student_db(
+    pool: &PgPool,
+    student_id: i32,
+    course_id: i32,
+) -> Result<String, EzyTutorError> {
+    let enrollment_row = sqlx::query!(
+        "DELETE FROM ezy_enrollment_c6 WHERE student_id = $1 and course_id = $2",
+        student_id,
+        course_id
+    )
+    .execute(pool)
+    .await?;
+
+    match enrollment_row.rows_affected() {
+        0 => Err(EzyTutorError::NotFound("Enrollment not found".into())),
+        rows => Ok(format!("Deleted {} record", rows)),
+    }
+}
+
+#[instrument(skip(pool))]
+pub async fn get_enrollments_for_student_db(
+    pool: &PgPool,
+    student_id: i32,
+) -> Result<Vec<StudentEnrollment>, EzyTutorError> {
+    let enrollment_rows = sqlx::query_as!(
+        StudentEnrollment,
+        "SELECT c.course_id, c.tutor_id, c.course_name, e.enrolled_time
+        FROM ezy_enrollment_c6 e JOIN ezy_course_c6 c ON c.course_id = e.course_id
+        WHERE e.student_id = $1
+        ORDER BY e.enrolled_time, c.course_id",
+        student_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(enrollment_rows)
+}
+
+#[instrument(skip(pool))]
+pub async fn get_course_roster_db(
+    pool: &PgPool,
+    tutor_id: i32,
+    course_id: i32,
+) -> Result<Vec<RosterEntry>, EzyTutorError> {
+    let roster_rows = sqlx::query_as!(
+        RosterEntry,
+        "SELECT s.student_id, s.student_name, s.student_email, e.enrolled_time
+        FROM ezy_enrollment_c6 e
+        JOIN ezy_student_c6 s ON s.student_id = e.student_id
+        JOIN ezy_course_c6 c ON c.course_id = e.course_id
+        WHERE c.tutor_id = $1 and c.course_id = $2
+        ORDER BY e.enrolled_time, s.student_id",
+        tutor_id,
+        course_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(roster_rows)
+}
@@ -1,2 +1,5 @@
+pub mod auth;
 pub mod course;
+pub mod enrollment;
+pub mod student;
 pub mod tutor;
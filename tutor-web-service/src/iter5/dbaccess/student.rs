@@ -0,0 +1,43 @@
+use crate::errors::EzyTutorError;
+use crate::models::student::{NewStudent, Student};
+use sqlx::postgres::PgPool;
+use tracing::instrument;
+
+#[instrument(skip(pool))]
+pub async fn get_student_details_db(
+    pool: &PgPool,
+    student_id: i32,
+) -> Result<Student, EzyTutorError> {
+    let student_row = sqlx::query_as!(
+        Student,
+        "SELECT student_id, student_name, student_email FROM ezy_student_c6 WHERE student_id = $1",
+        student_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(student) = student_row {
+        Ok(student)
+    } else {
+        Err(EzyTutorError::NotFound("Student id not found".into()))
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn post_new_student_db(
+    pool: &PgPool,
+    new_student: NewStudent,
+) -> Result<Student, EzyTutorError> {
+    // A taken email violates the UNIQUE constraint, which is answered with
+    // 409 Conflict
+    let student_row = sqlx::query_as!(
+        Student,
+        "INSERT INTO ezy_student_c6 (student_name, student_email) VALUES ($1, $2) returning student_id, student_name, student_email",
+        new_student.student_name,
+        new_student.student_email
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(student_row)
+}
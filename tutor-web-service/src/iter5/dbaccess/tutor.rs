@@ -1,12 +1,29 @@
 use crate::errors::EzyTutorError;
+use crate::models::paging::{Page, PageParams};
 use crate::models::tutor::{NewTutor, Tutor, UpdateTutor};
 use sqlx::postgres::PgPool;
+use tracing::instrument;
 
-pub async fn get_all_tutors_db(pool: &PgPool) -> Result<Vec<Tutor>, EzyTutorError> {
-    let tutor_rows =
-        sqlx::query!("SELECT tutor_id, tutor_name, tutor_pic_url, tutor_profile FROM ezy_tutor_c6")
-            .fetch_all(pool)
-            .await?;
+#[instrument(skip_all)]
+pub async fn get_all_tutors_db(
+    pool: &PgPool,
+    page_params: &PageParams,
+) -> Result<Page<Tutor>, EzyTutorError> {
+    let limit = page_params.limit()?;
+    let offset = page_params.offset()?;
+
+    let total = sqlx::query!(r#"SELECT COUNT(*) as "count!" FROM ezy_tutor_c6"#)
+        .fetch_one(pool)
+        .await?
+        .count;
+
+    let tutor_rows = sqlx::query!(
+        "SELECT tutor_id, tutor_name, tutor_pic_url, tutor_profile FROM ezy_tutor_c6 ORDER BY tutor_id LIMIT $1 OFFSET $2",
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await?;
 
     let tutors: Vec<Tutor> = tutor_rows
         .iter()
@@ -18,12 +35,10 @@ pub async fn get_all_tutors_db(pool: &PgPool) -> Result<Vec<Tutor>, EzyTutorErro
         })
         .collect();
 
-    match tutors.len() {
-        0 => Err(EzyTutorError::NotFound("No tutors Found".into())),
-        _ => Ok(tutors),
-    }
+    Ok(Page::new(tutors, total, limit, offset))
 }
 
+#[instrument(skip(pool))]
 pub async fn get_tutors_details_db(pool: &PgPool, tutor_id: i32) -> Result<Tutor, EzyTutorError> {
     let tutor_row = sqlx::query!(
         "SELECT tutor_id, tutor_name, tutor_pic_url, tutor_profile FROM ezy_tutor_c6 WHERE tutor_id = $1",
@@ -41,6 +56,7 @@ pub async fn get_tutors_details_db(pool: &PgPool, tutor_id: i32) -> Result<Tutor
     Ok(tutor_row)
 }
 
+#[instrument(skip_all)]
 pub async fn post_new_tutor_db(pool: &PgPool, new_tutor: NewTutor) -> Result<Tutor, EzyTutorError> {
     let tutor_row = sqlx::query!(
         "INSERT INTO ezy_tutor_c6 (tutor_name, tutor_pic_url, tutor_profile) VALUES ($1, $2, $3) returning tutor_id, tutor_name, tutor_pic_url, tutor_profile",
@@ -58,4 +74,62 @@ pub async fn post_new_tutor_db(pool: &PgPool, new_tutor: NewTutor) -> Result<Tut
     })
 }
 
-// todo update, delete
+#[instrument(skip(pool, update_tutor))]
+pub async fn update_tutor_details_db(
+    pool: &PgPool,
+    tutor_id: i32,
+    update_tutor: UpdateTutor,
+) -> Result<Tutor, EzyTutorError> {
+    // Fields left out of `update_tutor` keep their value
+    let tutor_row = sqlx::query!(
+        "UPDATE ezy_tutor_c6 SET tutor_name = COALESCE($1, tutor_name), tutor_pic_url = COALESCE($2, tutor_pic_url), tutor_profile = COALESCE($3, tutor_profile) WHERE tutor_id = $4 returning tutor_id, tutor_name, tutor_pic_url, tutor_profile",
+        update_tutor.tutor_name,
+        update_tutor.tutor_pic_url,
+        update_tutor.tutor_profile,
+        tutor_id
+    ).fetch_optional(pool)
+    .await?
+    .ok_or_else(|| EzyTutorError::NotFound("Tutor_id Not Found".into()))?;
+
+    Ok(Tutor {
+        tutor_id: tutor_row.tutor_id,
+        tutor_name: tutor_row.tutor_name,
+        tutor_pic_url: tutor_row.tutor_pic_url,
+        tutor_profile: tutor_row.tutor_profile,
+    })
+}
+
+// A tutor is only deleted once all of their courses are gone. Courses are
+// never removed implicitly, so callers have to delete them explicitly first.
+#[instrument(skip(pool))]
+pub async fn delete_tutor_db(pool: &PgPool, tutor_id: i32) -> Result<String, EzyTutorError> {
+    let mut tx = pool.begin().await?;
+
+    let course_count = sqlx::query!(
+        r#"SELECT COUNT(*) as "count!" FROM ezy_course_c6 WHERE tutor_id = $1"#,
+        tutor_id
+    )
+    .fetch_one(&mut tx)
+    .await?
+    .count;
+
+    if course_count > 0 {
+        return Err(EzyTutorError::Conflict(format!(
+            "Tutor {} still has {} course(s), delete them first",
+            tutor_id, course_count
+        )));
+    }
+
+    let tutor_row = sqlx::query!("DELETE FROM ezy_tutor_c6 WHERE tutor_id = $1", tutor_id)
+        .execute(&mut tx)
+        .await
+        .map_err(EzyTutorError::from_delete)?;
+
+    if tutor_row.rows_affected() == 0 {
+        return Err(EzyTutorError::NotFound("Tutor_id Not Found".into()));
+    }
+
+    tx.commit().await?;
+
+    Ok(format!("Deleted {} record", tutor_row.rows_affected()))
+}
@@ -0,0 +1,16 @@
+/* load data for testing, run after the migrations */
+INSERT INTO ezy_tutor_c6(tutor_id, tutor_name, tutor_pic_url, tutor_profile)
+VALUES (1, 'Merlene', 'http://s3.amazon.aws.com/pic1', 'Merlene is an experienced finance professional'),
+    (2, 'Frank', 'http://s3.amazon.aws.com/pic2', 'Frank is an expert nuclear engineer')
+ON CONFLICT (tutor_id) DO NOTHING;
+
+INSERT INTO ezy_course_c6(course_id, tutor_id, course_name, course_level, posted_time)
+VALUES (1, 1, 'First course', 'beginner', '2022-05-31 05:40:00')
+ON CONFLICT (course_id) DO NOTHING;
+
+INSERT INTO ezy_course_c6(course_id, tutor_id, course_name, course_format, posted_time)
+VALUES (2, 1, 'Second course', 'ebook', '2022-05-31 05:45:00')
+ON CONFLICT (course_id) DO NOTHING;
+
+SELECT setval('ezy_tutor_c6_tutor_id_seq', greatest((SELECT max(tutor_id) FROM ezy_tutor_c6), 1));
+SELECT setval('ezy_course_c6_course_id_seq', greatest((SELECT max(course_id) FROM ezy_course_c6), 1));
@@ -1,76 +0,0 @@
-use actix_web::{error, http::StatusCode, HttpResponse, Result};
-use serde::Serialize;
-use sqlx::error::Error as SQLxError;
-use std::fmt;
-
-#[derive(Debug, Serialize)]
-pub enum EzyTutorError {
-    DBError(String),
-    ActixError(String),
-    NotFound(String),
-    InvalidInput(String),
-}
-
-#[derive(Debug, Serialize)]
-pub struct MyErrorResponse {
-    error_message: String,
-}
-
-impl EzyTutorError {
-    fn error_response(&self) -> String {
-        match self {
-            EzyTutorError::DBError(msg) => {
-                println!("Database error ocurred: {:?}", msg);
-                "Database Error".into()
-            }
-            EzyTutorError::ActixError(msg) => {
-                println!("Server error ocurred: {:?}", msg);
-                "Internal Server Error".into()
-            }
-            EzyTutorError::NotFound(msg) => {
-                println!("Not found error ocurred: {:?}", msg);
-                msg.into()
-            }
-            EzyTutorError::InvalidInput(msg) => {
-                println!("Invalid parameters receieved: {:?}", msg);
-                msg.into()
-            }
-        }
-    }
-}
-
-impl error::ResponseError for EzyTutorError {
-    fn status_code(&self) -> StatusCode {
-        match self {
-            EzyTutorError::DBError(_) | EzyTutorError::ActixError(_) => {
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
-            EzyTutorError::NotFound(_) => StatusCode::NOT_FOUND,
-            EzyTutorError::InvalidInput(_msg) => StatusCode::BAD_REQUEST,
-        }
-    }
-
-    fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(MyErrorResponse {
-            error_message: self.error_response(),
-        })
-    }
-}
-
-impl std::fmt::Display for EzyTutorError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(f, "{}", self)
-    }
-}
-
-impl From<actix_web::error::Error> for EzyTutorError {
-    fn from(err: actix_web::error::Error) -> Self {
-        EzyTutorError::ActixError(err.to_string())
-    }
-}
-
-impl From<SQLxError> for EzyTutorError {
-    fn from(err: SQLxError) -> Self {
-        EzyTutorError::DBError(err.to_string())
-    }
-}
@@ -0,0 +1,43 @@
+use crate::auth::{generate_token, hash_token};
+use crate::dbaccess::auth::post_new_token_db;
+use crate::errors::EzyTutorError;
+use crate::models::auth::{Identity, NewApiToken, Role};
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+
+pub async fn get_identity(identity: Identity) -> Result<HttpResponse, EzyTutorError> {
+    Ok(HttpResponse::Ok().json(identity))
+}
+
+pub async fn post_new_token(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    new_token: web::Json<NewApiToken>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_admin()?;
+
+    let new_token = NewApiToken::from(new_token);
+    let ids_match_role = match new_token.role {
+        Role::Admin => new_token.tutor_id.is_none() && new_token.student_id.is_none(),
+        Role::Tutor => new_token.tutor_id.is_some() && new_token.student_id.is_none(),
+        Role::Student => new_token.tutor_id.is_none() && new_token.student_id.is_some(),
+    };
+    if !ids_match_role {
+        return Err(EzyTutorError::InvalidInput(format!(
+            "A {} token needs {}",
+            new_token.role,
+            match new_token.role {
+                Role::Admin => "neither tutor_id nor student_id",
+                Role::Tutor => "a tutor_id and no student_id",
+                Role::Student => "a student_id and no tutor_id",
+            }
+        )));
+    }
+
+    let token = generate_token();
+    let token_hash = hash_token(&token);
+
+    post_new_token_db(&app_state.db, token, &token_hash, new_token)
+        .await
+        .map(|token| HttpResponse::Ok().json(token))
+}
@@ -1,19 +1,31 @@
 use crate::dbaccess::course::*;
 use crate::errors::EzyTutorError;
-use crate::models::course::{CreateCourse, UpdateCourse};
+use crate::models::auth::Identity;
+use crate::models::course::{CourseQuery, CourseSearchQuery, CreateCourse, UpdateCourse};
 use crate::state::AppState;
 use actix_web::{web, HttpResponse};
-use std::convert::TryFrom;
+use tutor_common::metrics;
+use validator::Validate;
 
 pub async fn get_courses_for_tutor(
     app_state: web::Data<AppState>,
     params: web::Path<(i32,)>,
+    course_query: web::Query<CourseQuery>,
 ) -> Result<HttpResponse, EzyTutorError> {
-    get_courses_for_tutor_db(&app_state.db, params.0)
+    get_courses_for_tutor_db(&app_state.db, params.0, &course_query)
         .await
         .map(|courses| HttpResponse::Ok().json(courses))
 }
 
+pub async fn search_courses(
+    app_state: web::Data<AppState>,
+    search_query: web::Query<CourseSearchQuery>,
+) -> Result<HttpResponse, EzyTutorError> {
+    search_courses_db(&app_state.db, &search_query)
+        .await
+        .map(|results| HttpResponse::Ok().json(results))
+}
+
 pub async fn get_course_details(
     app_state: web::Data<AppState>,
     params: web::Path<(i32, i32)>,
@@ -25,17 +37,24 @@ pub async fn get_course_details(
 
 pub async fn post_new_course(
     app_state: web::Data<AppState>,
+    identity: Identity,
     new_course: web::Json<CreateCourse>,
 ) -> Result<HttpResponse, EzyTutorError> {
-    post_new_course_db(&app_state.db, new_course.into())
-        .await
-        .map(|course| HttpResponse::Ok().json(course))
+    identity.require_tutor(new_course.tutor_id)?;
+    new_course.validate()?;
+
+    let course = post_new_course_db(&app_state.db, new_course.into_inner()).await?;
+    metrics::COURSES_CREATED.inc();
+    Ok(HttpResponse::Ok().json(course))
 }
 
 pub async fn delete_course(
     app_state: web::Data<AppState>,
+    identity: Identity,
     params: web::Path<(i32, i32)>,
 ) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_tutor(params.0)?;
+
     delete_course_db(&app_state.db, params.0, params.1)
         .await
         .map(|resp| HttpResponse::Ok().json(resp))
@@ -43,24 +62,34 @@ pub async fn delete_course(
 
 pub async fn update_course_details(
     app_state: web::Data<AppState>,
+    identity: Identity,
     update_course: web::Json<UpdateCourse>,
     params: web::Path<(i32, i32)>,
 ) -> Result<HttpResponse, EzyTutorError> {
-    update_course_details_db(&app_state.db, params.0, params.1, update_course.into())
-        .await
-        .map(|course| HttpResponse::Ok().json(course))
+    identity.require_tutor(params.0)?;
+    update_course.validate()?;
+
+    update_course_details_db(
+        &app_state.db,
+        params.0,
+        params.1,
+        update_course.into_inner(),
+    )
+    .await
+    .map(|course| HttpResponse::Ok().json(course))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::models::course::{CourseFormat, CourseLevel, CourseSortField};
+    use crate::models::paging::SortOrder;
     use actix_web::http::StatusCode;
     use actix_web::ResponseError;
     //use chrono::NaiveDate;
     use dotenv::dotenv;
     use sqlx::postgres::PgPool;
     use std::env;
-    use std::sync::Mutex;
 
     #[actix_rt::test]
     async fn get_all_courses_success() {
@@ -69,18 +98,144 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let tutor_id: web::Path<(i32,)> = web::Path::from((1,));
-        let res = get_courses_for_tutor(app_state, tutor_id).await.unwrap();
+        let course_query = web::Query(CourseQuery::default());
+        let res = get_courses_for_tutor(app_state, tutor_id, course_query)
+            .await
+            .unwrap();
 
         assert_eq!(res.status(), StatusCode::OK)
     }
 
+    #[actix_rt::test]
+    async fn get_courses_paged_and_filtered() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let course_query = CourseQuery {
+            limit: Some(1),
+            sort_by: Some(CourseSortField::CourseName),
+            order: Some(SortOrder::Asc),
+            ..Default::default()
+        };
+        let page = get_courses_for_tutor_db(&db_pool, 1, &course_query)
+            .await
+            .unwrap();
+
+        assert_eq!(page.items.len(), 1);
+        assert!(page.total >= 2);
+        assert_eq!(page.next_cursor, Some(1));
+
+        let course_query = CourseQuery {
+            course_format: Some(CourseFormat::Live),
+            ..Default::default()
+        };
+        let page = get_courses_for_tutor_db(&db_pool, 1, &course_query)
+            .await
+            .unwrap();
+
+        assert_eq!(page.total, 0);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[actix_rt::test]
+    async fn get_courses_invalid_price_range() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let course_query = CourseQuery {
+            min_price_cents: Some(100),
+            max_price_cents: Some(10),
+            ..Default::default()
+        };
+        let res = get_courses_for_tutor_db(&db_pool, 1, &course_query).await;
+
+        match res {
+            Ok(_) => panic!("Expected an invalid price range to be rejected"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::BAD_REQUEST),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn search_courses_success() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let new_course_msg = CreateCourse {
+            tutor_id: 2,
+            course_name: "Quantum thermodynamics".into(),
+            course_description: Some("Heat engines at the <b>quantum</b> scale".into()),
+            course_format: Some(CourseFormat::Video),
+            course_level: Some(CourseLevel::Advanced),
+            course_price_cents: None,
+            course_currency: None,
+            course_duration_minutes: None,
+            course_lenguage: Some("English".into()),
+            course_structure: None,
+            course_capacity: None,
+        };
+        let course = post_new_course_db(&db_pool, new_course_msg.clone())
+            .await
+            .unwrap();
+        let beginner_course = post_new_course_db(
+            &db_pool,
+            CreateCourse {
+                course_name: "Thermodynamics for beginners".into(),
+                course_level: Some(CourseLevel::Beginner),
+                ..new_course_msg
+            },
+        )
+        .await
+        .unwrap();
+
+        let search_query = CourseSearchQuery {
+            q: Some("thermodynamic engines".into()),
+            course_level: Some(CourseLevel::Advanced),
+            ..Default::default()
+        };
+        let results = search_courses_db(&db_pool, &search_query).await.unwrap();
+
+        let hit = results
+            .page
+            .items
+            .iter()
+            .find(|hit| hit.course_id == course.course_id)
+            .expect("Created course not found by search");
+        assert!(hit.rank > 0.0);
+        assert!(hit.snippet.contains("<mark>"));
+        // Course text is escaped, only the highlighting is markup
+        assert!(hit.snippet.contains("&lt;b&gt;"));
+        assert!(!hit.snippet.contains("<b>"));
+        assert!(results
+            .page
+            .items
+            .iter()
+            .all(|hit| hit.course_level == Some(CourseLevel::Advanced)));
+        // The level facet is counted without the level filter
+        let levels: Vec<_> = results
+            .facets
+            .course_level
+            .iter()
+            .filter_map(|facet| facet.value.as_deref())
+            .collect();
+        assert!(levels.contains(&"advanced"));
+        assert!(levels.contains(&"beginner"));
+
+        for course in [course, beginner_course] {
+            delete_course_db(&db_pool, course.tutor_id, course.course_id)
+                .await
+                .unwrap();
+        }
+    }
+
     #[actix_rt::test]
     async fn get_course_detail_success_test() {
         dotenv().ok();
@@ -88,11 +243,7 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let params: web::Path<(i32, i32)> = web::Path::from((1, 2));
         let res = get_course_details(app_state, params).await.unwrap();
@@ -107,11 +258,7 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let params: web::Path<(i32, i32)> = web::Path::from((1, 21));
         let res = get_course_details(app_state, params).await;
@@ -129,31 +276,157 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let new_course_msg = CreateCourse {
             tutor_id: 1,
             course_name: "This is the next course".into(),
             course_description: Some("This is a test course".into()),
             course_format: None,
-            course_level: Some("Beginner".into()),
-            course_price: None,
-            course_duration: None,
+            course_level: Some(CourseLevel::Beginner),
+            course_price_cents: Some(1999),
+            course_currency: Some("EUR".into()),
+            course_duration_minutes: Some(90),
             course_lenguage: Some("English".into()),
             course_structure: None,
+            course_capacity: None,
         };
 
-        let res = post_new_course(app_state, web::Json(new_course_msg))
+        let res = post_new_course(app_state, Identity::tutor(1), web::Json(new_course_msg))
             .await
             .unwrap();
 
         assert_eq!(res.status(), StatusCode::OK)
     }
 
+    #[actix_rt::test]
+    async fn post_invalid_course_is_unprocessable() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
+
+        let new_course_msg = CreateCourse {
+            tutor_id: 1,
+            course_name: "x".repeat(200),
+            course_description: None,
+            course_format: None,
+            course_level: None,
+            course_price_cents: Some(-1),
+            course_currency: Some("usd".into()),
+            course_duration_minutes: None,
+            course_lenguage: None,
+            course_structure: None,
+            course_capacity: None,
+        };
+
+        let res = post_new_course(app_state, Identity::tutor(1), web::Json(new_course_msg)).await;
+
+        match res {
+            Ok(_) => panic!("Expected an invalid course to be rejected"),
+            Err(err) => {
+                assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+                let body = actix_web::body::to_bytes(err.error_response().into_body())
+                    .await
+                    .unwrap();
+                let body = std::str::from_utf8(&body).unwrap();
+                assert!(body.contains(
+                    r#"{"field":"course_name","reason":"must be at most 140 characters long"}"#
+                ));
+                assert!(body.contains(r#""field":"course_currency""#));
+                assert!(body
+                    .contains(r#"{"field":"course_price_cents","reason":"must be at least 0"}"#));
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn post_course_for_unknown_tutor_not_found() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let new_course_msg = CreateCourse {
+            tutor_id: i32::MAX,
+            course_name: "Course of nobody".into(),
+            course_description: None,
+            course_format: None,
+            course_level: None,
+            course_price_cents: None,
+            course_currency: None,
+            course_duration_minutes: None,
+            course_lenguage: None,
+            course_structure: None,
+            course_capacity: None,
+        };
+
+        match post_new_course_db(&db_pool, new_course_msg).await {
+            Ok(_) => panic!("Expected a course of an unknown tutor to be rejected"),
+            Err(err) => {
+                assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+                assert!(err.to_string().contains("fk_tutor"));
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn check_constraint_names_field() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        // Bypasses the request validation, so the database has to refuse it
+        let update_course_msg = UpdateCourse {
+            course_name: None,
+            course_description: None,
+            course_format: None,
+            course_level: None,
+            course_price_cents: Some(-100),
+            course_currency: None,
+            course_duration_minutes: None,
+            course_lenguage: None,
+            course_structure: None,
+            course_capacity: None,
+        };
+
+        match update_course_details_db(&db_pool, 1, 2, update_course_msg).await {
+            Ok(_) => panic!("Expected a negative price to be rejected"),
+            Err(EzyTutorError::ValidationFailed(errors)) => {
+                assert_eq!(errors[0].field, "course_price_cents");
+                assert!(errors[0].reason.contains("course_price_not_negative"));
+            }
+            Err(err) => panic!("Unexpected error {}", err),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn currency_without_cents_is_refused() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        // Prices are in cents, which yen do not have
+        let update_course_msg = UpdateCourse {
+            course_currency: Some("JPY".into()),
+            ..Default::default()
+        };
+        assert!(update_course_msg.validate().is_err());
+
+        match update_course_details_db(&db_pool, 1, 2, update_course_msg).await {
+            Ok(_) => panic!("Expected a currency without cents to be rejected"),
+            Err(EzyTutorError::ValidationFailed(errors)) => {
+                assert_eq!(errors[0].field, "course_currency");
+                assert!(errors[0].reason.contains("course_currency_iso"));
+            }
+            Err(err) => panic!("Unexpected error {}", err),
+        }
+    }
+
     #[actix_rt::test]
     async fn update_course_success() {
         dotenv().ok();
@@ -161,31 +434,71 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let new_course_msg = UpdateCourse {
             course_name: Some("Course name changed".into()),
             course_description: Some("This is a yet another test course".into()),
             course_format: None,
-            course_level: Some("Intermediate".into()),
-            course_price: None,
-            course_duration: None,
+            course_level: Some(CourseLevel::Intermediate),
+            course_price_cents: None,
+            course_currency: None,
+            course_duration_minutes: None,
             course_lenguage: Some("German".into()),
             course_structure: None,
+            course_capacity: None,
         };
 
         let parameters = web::Path::from((1, 2));
-        let res = update_course_details(app_state, web::Json(new_course_msg), parameters)
-            .await
-            .unwrap();
+        let res = update_course_details(
+            app_state,
+            Identity::tutor(1),
+            web::Json(new_course_msg),
+            parameters,
+        )
+        .await
+        .unwrap();
 
         assert_eq!(res.status(), StatusCode::OK)
     }
 
+    #[actix_rt::test]
+    async fn update_course_of_other_tutor_forbidden() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
+
+        let update_course_msg = UpdateCourse {
+            course_name: Some("Hijacked course".into()),
+            course_description: None,
+            course_format: None,
+            course_level: None,
+            course_price_cents: None,
+            course_currency: None,
+            course_duration_minutes: None,
+            course_lenguage: None,
+            course_structure: None,
+            course_capacity: None,
+        };
+
+        let parameters = web::Path::from((1, 2));
+        let res = update_course_details(
+            app_state,
+            Identity::tutor(2),
+            web::Json(update_course_msg),
+            parameters,
+        )
+        .await;
+
+        match res {
+            Ok(_) => panic!("Expected another tutor's course update to be rejected"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::FORBIDDEN),
+        }
+    }
+
     #[actix_rt::test]
     async fn delete_test_success() {
         dotenv().ok();
@@ -193,14 +506,12 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let parameters = web::Path::from((1, 5));
-        let res = delete_course(app_state, parameters).await.unwrap();
+        let res = delete_course(app_state, Identity::admin(), parameters)
+            .await
+            .unwrap();
 
         assert_eq!(res.status(), StatusCode::OK)
     }
@@ -212,14 +523,10 @@ mod test {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
 
         let parameters = web::Path::from((1, 21));
-        let res = delete_course(app_state, parameters).await;
+        let res = delete_course(app_state, Identity::admin(), parameters).await;
 
         match res {
             Ok(_) => println!("Something wrong"),
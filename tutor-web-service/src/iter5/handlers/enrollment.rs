@@ -0,0 +1,252 @@
+use crate::dbaccess::course::get_course_details_db;
+use crate::dbaccess::enrollment::*;
+use crate::dbaccess::student::get_student_details_db;
+use crate::errors::EzyTutorError;
+use crate::models::auth::Identity;
+use crate::models::enrollment::NewEnrollment;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use tutor_common::metrics;
+
+pub async fn enroll_student(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    student_id: web::Path<i32>,
+    new_enrollment: web::Json<NewEnrollment>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_student(*student_id)?;
+
+    let enrollment = enroll_student_db(&app_state.db, *student_id, new_enrollment.into()).await?;
+    metrics::STUDENTS_ENROLLED.inc();
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+pub async fn unenroll_student(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    params: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_student(params.0)?;
+
+    unenroll_student_db(&app_state.db, params.0, params.1)
+        .await
+        .map(|resp| HttpResponse::Ok().json(resp))
+}
+
+pub async fn get_enrollments_for_student(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    student_id: web::Path<i32>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_student(*student_id)?;
+
+    get_student_details_db(&app_state.db, *student_id).await?;
+
+    get_enrollments_for_student_db(&app_state.db, *student_id)
+        .await
+        .map(|enrollments| HttpResponse::Ok().json(enrollments))
+}
+
+pub async fn get_course_roster(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    params: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_tutor(params.0)?;
+
+    get_course_details_db(&app_state.db, params.0, params.1).await?;
+
+    get_course_roster_db(&app_state.db, params.0, params.1)
+        .await
+        .map(|roster| HttpResponse::Ok().json(roster))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dbaccess::course::{delete_course_db, post_new_course_db};
+    use crate::dbaccess::student::post_new_student_db;
+    use crate::models::course::{Course, CreateCourse};
+    use crate::models::student::{NewStudent, Student};
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPool;
+    use std::env;
+
+    async fn test_app_state() -> web::Data<AppState> {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        web::Data::new(AppState { db: db_pool })
+    }
+
+    async fn new_test_course(app_state: &web::Data<AppState>, capacity: Option<i32>) -> Course {
+        let new_course = CreateCourse {
+            tutor_id: 1,
+            course_name: "Enrollment test course".into(),
+            course_description: None,
+            course_format: None,
+            course_structure: None,
+            course_duration_minutes: None,
+            course_price_cents: None,
+            course_currency: None,
+            course_lenguage: None,
+            course_level: None,
+            course_capacity: capacity,
+        };
+
+        post_new_course_db(&app_state.db, new_course).await.unwrap()
+    }
+
+    // Each test uses students of its own, left over ones of an earlier failed
+    // run are deleted first
+    async fn new_test_student(app_state: &web::Data<AppState>, name: &str) -> Student {
+        let student_email = format!("{}@ezytutors.com", name);
+        sqlx::query!(
+            "DELETE FROM ezy_enrollment_c6 WHERE student_id IN
+            (SELECT student_id FROM ezy_student_c6 WHERE student_email = $1)",
+            student_email
+        )
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "DELETE FROM ezy_student_c6 WHERE student_email = $1",
+            student_email
+        )
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+
+        let new_student = NewStudent {
+            student_name: "Test student".into(),
+            student_email,
+        };
+        post_new_student_db(&app_state.db, new_student)
+            .await
+            .unwrap()
+    }
+
+    // Deletes the course with its enrollments, then the students
+    async fn clean_up(app_state: &web::Data<AppState>, course: Course, students: &[Student]) {
+        delete_course_db(&app_state.db, course.tutor_id, course.course_id)
+            .await
+            .unwrap();
+        for student in students {
+            sqlx::query!(
+                "DELETE FROM ezy_student_c6 WHERE student_id = $1",
+                student.student_id
+            )
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[actix_rt::test]
+    async fn enroll_and_unenroll_success() {
+        let app_state = test_app_state().await;
+        let course = new_test_course(&app_state, None).await;
+        let student = new_test_student(&app_state, "enroll-and-unenroll").await;
+
+        let res = enroll_student(
+            app_state.clone(),
+            Identity::student(student.student_id),
+            web::Path::from(student.student_id),
+            web::Json(NewEnrollment {
+                course_id: course.course_id,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let enrollments = get_enrollments_for_student_db(&app_state.db, student.student_id)
+            .await
+            .unwrap();
+        assert_eq!(enrollments.len(), 1);
+        assert_eq!(enrollments[0].course_id, course.course_id);
+
+        let roster = get_course_roster_db(&app_state.db, course.tutor_id, course.course_id)
+            .await
+            .unwrap();
+        assert_eq!(roster.len(), 1);
+        assert_eq!(roster[0].student_id, student.student_id);
+
+        let res = unenroll_student(
+            app_state.clone(),
+            Identity::student(student.student_id),
+            web::Path::from((student.student_id, course.course_id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        clean_up(&app_state, course, &[student]).await;
+    }
+
+    #[actix_rt::test]
+    async fn enroll_twice_failure() {
+        let app_state = test_app_state().await;
+        let course = new_test_course(&app_state, None).await;
+        let student = new_test_student(&app_state, "enroll-twice").await;
+
+        let enrollment = NewEnrollment {
+            course_id: course.course_id,
+        };
+        enroll_student_db(&app_state.db, student.student_id, enrollment.clone())
+            .await
+            .unwrap();
+        let res = enroll_student_db(&app_state.db, student.student_id, enrollment).await;
+
+        match res {
+            Ok(_) => panic!("Expected a duplicate enrollment to be rejected"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::CONFLICT),
+        }
+
+        clean_up(&app_state, course, &[student]).await;
+    }
+
+    #[actix_rt::test]
+    async fn enroll_full_course_failure() {
+        let app_state = test_app_state().await;
+        let course = new_test_course(&app_state, Some(1)).await;
+        let first_student = new_test_student(&app_state, "full-course-first").await;
+        let second_student = new_test_student(&app_state, "full-course-second").await;
+
+        let enrollment = NewEnrollment {
+            course_id: course.course_id,
+        };
+        enroll_student_db(&app_state.db, first_student.student_id, enrollment.clone())
+            .await
+            .unwrap();
+        let res = enroll_student_db(&app_state.db, second_student.student_id, enrollment).await;
+
+        match res {
+            Ok(_) => panic!("Expected enrollment in a full course to be rejected"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::CONFLICT),
+        }
+
+        clean_up(&app_state, course, &[first_student, second_student]).await;
+    }
+
+    #[actix_rt::test]
+    async fn unenroll_failure() {
+        let app_state = test_app_state().await;
+
+        let res = unenroll_student(
+            app_state,
+            Identity::admin(),
+            web::Path::from((i32::MAX, i32::MAX)),
+        )
+        .await;
+
+        match res {
+            Ok(_) => panic!("Expected a missing enrollment to be reported"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::NOT_FOUND),
+        }
+    }
+}
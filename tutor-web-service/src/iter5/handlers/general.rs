@@ -1,12 +1,66 @@
-use crate::errors::EzyTutorError;
+use crate::migrate::migrator;
 use crate::state::AppState;
 use actix_web::{web, HttpResponse};
+use std::collections::BTreeMap;
+use tutor_common::health::{self, HealthReport};
+use tutor_common::metrics;
 
-pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpResponse {
-    let health_check_response = &app_state.health_check_response;
-    let mut visit_count = app_state.visit_count.lock().unwrap();
-    let response = format!("{} {} times", health_check_response, visit_count);
-    *visit_count += 1;
+pub async fn liveness_handler() -> HttpResponse {
+    health::live()
+}
+
+// Ready once the database answers and its schema is up to date
+pub async fn readiness_handler(app_state: web::Data<AppState>) -> HttpResponse {
+    let components = BTreeMap::from([
+        ("database", health::check_database(&app_state.db).await),
+        (
+            "migrations",
+            health::check_migrations(&app_state.db, &migrator()).await,
+        ),
+    ]);
+
+    HealthReport::new(components).into_response()
+}
+
+pub async fn metrics_handler(app_state: web::Data<AppState>) -> HttpResponse {
+    metrics::render(&app_state.db)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPool;
+    use std::env;
+
+    async fn test_app_state() -> web::Data<AppState> {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+        web::Data::new(AppState { db: db_pool })
+    }
+
+    fn body_text(resp: HttpResponse) -> String {
+        let body = resp.into_body().try_into_bytes().unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn readiness_reports_each_component() {
+        let resp = readiness_handler(test_app_state().await).await;
+        let body = body_text(resp);
+        assert!(body.contains(r#""database":{"status":"up","pool_idle":"#));
+        assert!(body.contains(r#""migrations":{"status":"#));
+    }
 
-    HttpResponse::Ok().json(&response)
+    #[actix_rt::test]
+    async fn metrics_include_pool_usage() {
+        let resp = metrics_handler(test_app_state().await).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = body_text(resp);
+        assert!(body.contains("db_pool_connections{state=\"idle\"}"));
+        assert!(body.contains("courses_created_total"));
+    }
 }
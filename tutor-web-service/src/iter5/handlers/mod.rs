@@ -1,3 +1,6 @@
+pub mod auth;
 pub mod course;
+pub mod enrollment;
 pub mod general;
+pub mod student;
 pub mod tutor;
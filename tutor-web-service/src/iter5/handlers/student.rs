@@ -0,0 +1,100 @@
+use crate::dbaccess::student::*;
+use crate::errors::EzyTutorError;
+use crate::models::auth::Identity;
+use crate::models::student::NewStudent;
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use validator::Validate;
+
+pub async fn get_student_details(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    student_id: web::Path<i32>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_student(*student_id)?;
+
+    get_student_details_db(&app_state.db, *student_id)
+        .await
+        .map(|student| HttpResponse::Ok().json(student))
+}
+
+pub async fn post_new_student(
+    app_state: web::Data<AppState>,
+    identity: Identity,
+    new_student: web::Json<NewStudent>,
+) -> Result<HttpResponse, EzyTutorError> {
+    identity.require_admin()?;
+    new_student.validate()?;
+
+    post_new_student_db(&app_state.db, NewStudent::from(new_student))
+        .await
+        .map(|student| HttpResponse::Ok().json(student))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPool;
+    use std::env;
+
+    #[actix_rt::test]
+    async fn post_student_with_taken_email_conflict() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+        sqlx::query!("DELETE FROM ezy_student_c6 WHERE student_email = 'taken@ezytutors.com'")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
+        let new_student = || {
+            web::Json(NewStudent {
+                student_name: "Taken".into(),
+                student_email: "taken@ezytutors.com".into(),
+            })
+        };
+
+        let res = post_new_student(app_state.clone(), Identity::admin(), new_student())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        match post_new_student(app_state.clone(), Identity::admin(), new_student()).await {
+            Ok(_) => panic!("Expected a taken email to be rejected"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::CONFLICT),
+        }
+
+        sqlx::query!("DELETE FROM ezy_student_c6 WHERE student_email = 'taken@ezytutors.com'")
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn post_invalid_student_lists_fields() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+        let app_state: web::Data<AppState> = web::Data::new(AppState { db: db_pool });
+
+        let new_student = web::Json(NewStudent {
+            student_name: " ".into(),
+            student_email: "not an email".into(),
+        });
+        match post_new_student(app_state, Identity::admin(), new_student).await {
+            Ok(_) => panic!("Expected an invalid student to be rejected"),
+            Err(EzyTutorError::ValidationFailed(errors)) => {
+                let fields: Vec<&str> = errors.iter().map(|err| err.field.as_str()).collect();
+                assert_eq!(fields, ["student_email", "student_name"]);
+                assert_eq!(errors[0].reason, "must be a valid email address");
+            }
+            Err(err) => panic!("Unexpected error {}", err),
+        }
+    }
+}
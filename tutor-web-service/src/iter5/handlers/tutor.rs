@@ -1,11 +1,18 @@
 use crate::dbaccess::tutor::*;
 use crate::errors::EzyTutorError;
+use crate::models::auth::Identity;
+use crate::models::paging::PageParams;
 use crate::models::tutor::{NewTutor, UpdateTutor};
 use crate::state::AppState;
 use actix_web::{web, HttpResponse};
+use tutor_common::metrics;
+use validator::Validate;
 
-pub async fn get_all_tutors(app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
-    get_all_tutors_db(&app_state.db)
+pub async fn get_all_tutors(
+    app_state: web::Data<AppState>,
+    page_params: web::Query<PageParams>,
+) -> Result<HttpResponse, EzyTutorError> {
+    get_all_tutors_db(&app_state.db, &page_params)
         .await
         .map(|tutors| HttpResponse::Ok().json(tutors))
 }
@@ -21,29 +28,202 @@ pub async fn get_tutor_details(
 
 pub async fn post_new_tutor(
     app_state: web::Data<AppState>,
+    identity: Identity,
     new_tutor: web::Json<NewTutor>,
 ) -> Result<HttpResponse, EzyTutorError> {
-    post_new_tutor_db(&app_state.db, NewTutor::from(new_tutor))
-        .await
-        .map(|tutor| HttpResponse::Ok().json(tutor))
+    identity.require_admin()?;
+    new_tutor.validate()?;
+
+    let tutor = post_new_tutor_db(&app_state.db, new_tutor.into_inner()).await?;
+    metrics::TUTORS_REGISTERED.inc();
+    Ok(HttpResponse::Ok().json(tutor))
 }
 
-/* pub async fn update_tutor_details(
+pub async fn update_tutor_details(
     app_state: web::Data<AppState>,
+    identity: Identity,
     tutor_id: web::Path<i32>,
     update_tutor: web::Json<UpdateTutor>,
 ) -> Result<HttpResponse, EzyTutorError> {
-    update_tutor_details_db(&app_state.db, tutor_id, UpdateTutor::from(update_tutor))
+    identity.require_tutor(*tutor_id)?;
+    update_tutor.validate()?;
+
+    update_tutor_details_db(&app_state.db, *tutor_id, update_tutor.into_inner())
         .await
         .map(|tutor| HttpResponse::Ok().json(tutor))
 }
 
 pub async fn delete_tutor(
     app_state: web::Data<AppState>,
+    identity: Identity,
     tutor_id: web::Path<i32>,
 ) -> Result<HttpResponse, EzyTutorError> {
-    delete_tutor_db(&app_state.db, tutor_id)
+    identity.require_tutor(*tutor_id)?;
+
+    delete_tutor_db(&app_state.db, *tutor_id)
         .await
-        .map(|tutor| HttpResponse::Ok().json(tutor))
+        .map(|resp| HttpResponse::Ok().json(resp))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dbaccess::course::{delete_course_db, post_new_course_db};
+    use crate::models::course::CreateCourse;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPool;
+    use std::env;
+
+    async fn test_app_state() -> web::Data<AppState> {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        web::Data::new(AppState { db: db_pool })
+    }
+
+    async fn new_test_tutor(app_state: &web::Data<AppState>) -> i32 {
+        let new_tutor = NewTutor {
+            tutor_name: "Test tutor".into(),
+            tutor_pic_url: "http://tutor.com/tutor.png".into(),
+            tutor_profile: "Test tutor profile".into(),
+        };
+
+        post_new_tutor_db(&app_state.db, new_tutor)
+            .await
+            .unwrap()
+            .tutor_id
+    }
+
+    #[actix_rt::test]
+    async fn update_tutor_success() {
+        let app_state = test_app_state().await;
+        let tutor_id = new_test_tutor(&app_state).await;
+
+        let update_tutor_msg = UpdateTutor {
+            tutor_name: Some("Updated tutor".into()),
+            tutor_pic_url: None,
+            tutor_profile: None,
+        };
+
+        let res = update_tutor_details(
+            app_state.clone(),
+            Identity::tutor(tutor_id),
+            web::Path::from(tutor_id),
+            web::Json(update_tutor_msg),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let tutor = get_tutors_details_db(&app_state.db, tutor_id)
+            .await
+            .unwrap();
+        assert_eq!(tutor.tutor_name, "Updated tutor");
+        assert_eq!(tutor.tutor_profile, "Test tutor profile");
+    }
+
+    #[actix_rt::test]
+    async fn update_tutor_failure() {
+        let app_state = test_app_state().await;
+
+        let update_tutor_msg = UpdateTutor {
+            tutor_name: Some("Updated tutor".into()),
+            tutor_pic_url: None,
+            tutor_profile: None,
+        };
+
+        let res = update_tutor_details(
+            app_state,
+            Identity::admin(),
+            web::Path::from(i32::MAX),
+            web::Json(update_tutor_msg),
+        )
+        .await;
+
+        match res {
+            Ok(_) => panic!("Expected tutor update to fail"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::NOT_FOUND),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn delete_tutor_success() {
+        let app_state = test_app_state().await;
+        let tutor_id = new_test_tutor(&app_state).await;
+
+        let res = delete_tutor(
+            app_state.clone(),
+            Identity::admin(),
+            web::Path::from(tutor_id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let tutor = get_tutors_details_db(&app_state.db, tutor_id).await;
+        assert!(tutor.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn delete_tutor_with_courses_failure() {
+        let app_state = test_app_state().await;
+        let tutor_id = new_test_tutor(&app_state).await;
+        let new_course = CreateCourse {
+            tutor_id,
+            course_name: "Test course".into(),
+            course_description: None,
+            course_format: None,
+            course_structure: None,
+            course_duration_minutes: None,
+            course_price_cents: None,
+            course_currency: None,
+            course_lenguage: None,
+            course_level: None,
+            course_capacity: None,
+        };
+        let course = post_new_course_db(&app_state.db, new_course).await.unwrap();
+
+        let res = delete_tutor(
+            app_state.clone(),
+            Identity::tutor(tutor_id),
+            web::Path::from(tutor_id),
+        )
+        .await;
+
+        delete_course_db(&app_state.db, tutor_id, course.course_id)
+            .await
+            .unwrap();
+        delete_tutor_db(&app_state.db, tutor_id).await.unwrap();
+
+        match res {
+            Ok(_) => panic!("Expected tutor with courses not to be deleted"),
+            Err(err) => assert_eq!(err.status_code(), StatusCode::CONFLICT),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn post_invalid_tutor_lists_fields() {
+        let app_state = test_app_state().await;
+
+        let new_tutor = NewTutor {
+            tutor_name: " ".into(),
+            tutor_pic_url: "not a url".into(),
+            tutor_profile: "Test tutor profile".into(),
+        };
+        let res = post_new_tutor(app_state, Identity::admin(), web::Json(new_tutor)).await;
+
+        match res {
+            Ok(_) => panic!("Expected an invalid tutor to be rejected"),
+            Err(EzyTutorError::ValidationFailed(errors)) => {
+                let fields: Vec<&str> = errors.iter().map(|err| err.field.as_str()).collect();
+                assert_eq!(fields, ["tutor_name", "tutor_pic_url"]);
+                assert_eq!(errors[1].reason, "must be a valid URL");
+            }
+            Err(err) => panic!("Unexpected error {}", err),
+        }
+    }
 }
- */
@@ -0,0 +1,29 @@
+use sqlx::migrate::Migrator;
+
+// The migrations in tutor-web-service/migrations, embedded at compile time.
+// The web app keeps its own migrations in the same database, so versions
+// applied by it are not reported as missing here.
+pub fn migrator() -> Migrator {
+    let mut migrator = sqlx::migrate!();
+    migrator.set_ignore_missing(true);
+    migrator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_migration_can_be_reverted() {
+        let migrator = migrator();
+        let (down, up): (Vec<_>, Vec<_>) = migrator
+            .iter()
+            .partition(|migration| migration.migration_type.is_down_migration());
+
+        assert!(!up.is_empty());
+        let up_versions: Vec<i64> = up.iter().map(|migration| migration.version).collect();
+        let mut down_versions: Vec<i64> = down.iter().map(|migration| migration.version).collect();
+        down_versions.sort_unstable();
+        assert_eq!(up_versions, down_versions);
+    }
+}
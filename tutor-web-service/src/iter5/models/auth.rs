@@ -0,0 +1,98 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Tutor,
+    Student,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let role = match self {
+            Role::Admin => "admin",
+            Role::Tutor => "tutor",
+            Role::Student => "student",
+        };
+        write!(f, "{}", role)
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "admin" => Ok(Role::Admin),
+            "tutor" => Ok(Role::Tutor),
+            "student" => Ok(Role::Student),
+            other => Err(format!("Unknown role {}", other)),
+        }
+    }
+}
+
+// The caller behind an API token. `tutor_id` is set for tutors and
+// `student_id` for students, admins carry neither.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Identity {
+    pub role: Role,
+    pub tutor_id: Option<i32>,
+    pub student_id: Option<i32>,
+}
+
+#[cfg(test)]
+impl Identity {
+    pub fn admin() -> Self {
+        Identity {
+            role: Role::Admin,
+            tutor_id: None,
+            student_id: None,
+        }
+    }
+
+    pub fn tutor(tutor_id: i32) -> Self {
+        Identity {
+            role: Role::Tutor,
+            tutor_id: Some(tutor_id),
+            student_id: None,
+        }
+    }
+
+    pub fn student(student_id: i32) -> Self {
+        Identity {
+            role: Role::Student,
+            tutor_id: None,
+            student_id: Some(student_id),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NewApiToken {
+    pub role: Role,
+    pub tutor_id: Option<i32>,
+    pub student_id: Option<i32>,
+}
+
+// Returned once when a token is issued, only its hash is stored
+#[derive(Debug, Serialize, Clone)]
+pub struct ApiToken {
+    pub token: String,
+    pub role: Role,
+    pub tutor_id: Option<i32>,
+    pub student_id: Option<i32>,
+}
+
+impl From<web::Json<NewApiToken>> for NewApiToken {
+    fn from(new_token: web::Json<NewApiToken>) -> Self {
+        Self {
+            role: new_token.role,
+            tutor_id: new_token.tutor_id,
+            student_id: new_token.student_id,
+        }
+    }
+}
@@ -1,75 +1,134 @@
-use actix_web::web;
+use super::paging::{Page, PageParams, SortOrder};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
-pub struct Course {
+pub use tutor_api::course::{
+    Course, CourseFormat, CourseLevel, CreateCourse, UpdateCourse, DEFAULT_CURRENCY,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CourseSortField {
+    PostedTime,
+    CoursePrice,
+    CourseName,
+}
+
+impl CourseSortField {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            CourseSortField::PostedTime => "posted_time",
+            CourseSortField::CoursePrice => "course_price_cents",
+            CourseSortField::CourseName => "course_name",
+        }
+    }
+}
+
+// Query string accepted by course listings, e.g.
+// `/courses/1?course_level=beginner&sort_by=course_price&order=desc&limit=10`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CourseQuery {
+    pub limit: Option<i64>,
+    #[serde(alias = "cursor")]
+    pub offset: Option<i64>,
+    pub sort_by: Option<CourseSortField>,
+    pub order: Option<SortOrder>,
+    pub course_level: Option<CourseLevel>,
+    pub course_format: Option<CourseFormat>,
+    pub course_lenguage: Option<String>,
+    pub min_price_cents: Option<i32>,
+    pub max_price_cents: Option<i32>,
+}
+
+impl CourseQuery {
+    pub fn page(&self) -> PageParams {
+        PageParams {
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+}
+
+// Query string accepted by `/courses/search`. Without `q` the whole catalog is
+// listed, newest first.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CourseSearchQuery {
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    #[serde(alias = "cursor")]
+    pub offset: Option<i64>,
+    pub course_level: Option<CourseLevel>,
+    pub course_format: Option<CourseFormat>,
+    pub course_lenguage: Option<String>,
+}
+
+impl CourseSearchQuery {
+    pub fn page(&self) -> PageParams {
+        PageParams {
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+
+    // Blank search terms behave like a plain catalog listing
+    pub fn terms(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CourseSearchHit {
     pub course_id: i32,
     pub tutor_id: i32,
     pub course_name: String,
     pub course_description: Option<String>,
-    pub course_format: Option<String>,
+    pub course_format: Option<CourseFormat>,
     pub course_structure: Option<String>,
-    pub course_duration: Option<String>,
-    pub course_price: Option<i32>,
+    pub course_duration_minutes: Option<i32>,
+    pub course_price_cents: Option<i32>,
+    pub course_currency: String,
     pub course_lenguage: Option<String>,
-    pub course_level: Option<String>,
+    pub course_level: Option<CourseLevel>,
+    pub course_capacity: Option<i32>,
     pub posted_time: Option<NaiveDateTime>,
+    pub rank: f32,
+    pub snippet: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct CreateCourse {
-    pub tutor_id: i32,
-    pub course_name: String,
-    pub course_description: Option<String>,
-    pub course_format: Option<String>,
-    pub course_structure: Option<String>,
-    pub course_duration: Option<String>,
-    pub course_price: Option<i32>,
-    pub course_lenguage: Option<String>,
-    pub course_level: Option<String>,
+#[derive(Debug, Clone, Serialize)]
+pub struct FacetCount {
+    pub value: Option<String>,
+    pub count: i64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct UpdateCourse {
-    pub course_name: Option<String>,
-    pub course_description: Option<String>,
-    pub course_format: Option<String>,
-    pub course_structure: Option<String>,
-    pub course_duration: Option<String>,
-    pub course_price: Option<i32>,
-    pub course_lenguage: Option<String>,
-    pub course_level: Option<String>,
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CourseFacets {
+    pub course_level: Vec<FacetCount>,
+    pub course_format: Vec<FacetCount>,
+    pub course_lenguage: Vec<FacetCount>,
 }
 
-impl From<web::Json<CreateCourse>> for CreateCourse {
-    fn from(new_course: web::Json<CreateCourse>) -> Self {
-        Self {
-            tutor_id: new_course.tutor_id,
-            course_name: new_course.course_name.clone(),
-            course_description: new_course.course_description.clone(),
-            course_format: new_course.course_format.clone(),
-            course_structure: new_course.course_structure.clone(),
-            course_level: new_course.course_level.clone(),
-            course_duration: new_course.course_duration.clone(),
-            course_lenguage: new_course.course_lenguage.clone(),
-            course_price: new_course.course_price,
-        }
-    }
+#[derive(Debug, Clone, Serialize)]
+pub struct CourseSearchResults {
+    #[serde(flatten)]
+    pub page: Page<CourseSearchHit>,
+    pub facets: CourseFacets,
 }
 
-impl From<web::Json<UpdateCourse>> for UpdateCourse {
-    fn from(new_course: web::Json<UpdateCourse>) -> Self {
-        Self {
-            course_name: new_course.course_name.clone(),
-            course_description: new_course.course_description.clone(),
-            course_format: new_course.course_format.clone(),
-            course_structure: new_course.course_structure.clone(),
-            course_level: new_course.course_level.clone(),
-            course_duration: new_course.course_duration.clone(),
-            course_lenguage: new_course.course_lenguage.clone(),
-            course_price: new_course.course_price,
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::web;
+
+    #[test]
+    fn course_filters_are_typed() {
+        let query =
+            web::Query::<CourseQuery>::from_query("course_level=Beginner&course_format=in_person")
+                .unwrap();
+        assert_eq!(query.course_level, Some(CourseLevel::Beginner));
+        assert_eq!(query.course_format, Some(CourseFormat::InPerson));
+
+        assert!(web::Query::<CourseQuery>::from_query("course_level=expert").is_err());
+        assert!(web::Query::<CourseQuery>::from_query("course_format=pdf").is_err());
     }
 }
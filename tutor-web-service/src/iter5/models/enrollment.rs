@@ -0,0 +1,41 @@
+use actix_web::web;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Enrollment {
+    pub student_id: i32,
+    pub course_id: i32,
+    pub enrolled_time: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NewEnrollment {
+    pub course_id: i32,
+}
+
+// A course as seen from the enrollments of one student
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StudentEnrollment {
+    pub course_id: i32,
+    pub tutor_id: i32,
+    pub course_name: String,
+    pub enrolled_time: Option<NaiveDateTime>,
+}
+
+// A student as seen from the roster of one course
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RosterEntry {
+    pub student_id: i32,
+    pub student_name: String,
+    pub student_email: String,
+    pub enrolled_time: Option<NaiveDateTime>,
+}
+
+impl From<web::Json<NewEnrollment>> for NewEnrollment {
+    fn from(new_enrollment: web::Json<NewEnrollment>) -> Self {
+        Self {
+            course_id: new_enrollment.course_id,
+        }
+    }
+}
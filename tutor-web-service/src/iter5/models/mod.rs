@@ -1,2 +1,6 @@
+pub mod auth;
 pub mod course;
+pub mod enrollment;
+pub mod paging;
+pub mod student;
 pub mod tutor;
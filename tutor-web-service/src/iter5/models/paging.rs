@@ -0,0 +1,88 @@
+use crate::errors::EzyTutorError;
+use serde::{Deserialize, Serialize};
+
+pub use tutor_api::page::Page;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+// Offset based paging parameters. `cursor` is accepted as an alias of
+// `offset` so clients can feed `next_cursor` straight back in.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PageParams {
+    pub limit: Option<i64>,
+    #[serde(alias = "cursor")]
+    pub offset: Option<i64>,
+}
+
+impl PageParams {
+    pub fn limit(&self) -> Result<i64, EzyTutorError> {
+        match self.limit {
+            None => Ok(DEFAULT_PAGE_LIMIT),
+            Some(limit) if limit < 1 => Err(EzyTutorError::InvalidInput(
+                "limit must be greater than zero".into(),
+            )),
+            Some(limit) => Ok(limit.min(MAX_PAGE_LIMIT)),
+        }
+    }
+
+    pub fn offset(&self) -> Result<i64, EzyTutorError> {
+        match self.offset {
+            None => Ok(0),
+            Some(offset) if offset < 0 => Err(EzyTutorError::InvalidInput(
+                "offset must not be negative".into(),
+            )),
+            Some(offset) => Ok(offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn page_has_next_cursor_until_last_item() {
+        let page = Page::new(vec![1, 2], 5, 2, 0);
+        assert_eq!(page.next_cursor, Some(2));
+
+        let page = Page::new(vec![5], 5, 2, 4);
+        assert_eq!(page.next_cursor, None);
+
+        let page: Page<i32> = Page::new(vec![], 5, 2, 10);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn page_params_are_bounded() {
+        let params = PageParams {
+            limit: Some(1000),
+            offset: None,
+        };
+        assert_eq!(params.limit().unwrap(), MAX_PAGE_LIMIT);
+        assert_eq!(params.offset().unwrap(), 0);
+
+        let params = PageParams {
+            limit: Some(0),
+            offset: Some(-1),
+        };
+        assert!(params.limit().is_err());
+        assert!(params.offset().is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use tutor_api::validation::not_blank;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Student {
+    pub student_id: i32,
+    pub student_name: String,
+    pub student_email: String,
+}
+
+// Limits follow the column sizes of ezy_student_c6
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct NewStudent {
+    #[validate(length(max = 200), custom = "not_blank")]
+    pub student_name: String,
+    #[validate(length(max = 200), email)]
+    pub student_email: String,
+}
+
+impl From<web::Json<NewStudent>> for NewStudent {
+    fn from(new_student: web::Json<NewStudent>) -> Self {
+        Self {
+            student_name: new_student.student_name.clone(),
+            student_email: new_student.student_email.clone(),
+        }
+    }
+}
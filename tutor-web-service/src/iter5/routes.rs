@@ -1,21 +1,29 @@
-use crate::handlers::{course::*, general::*, tutor::*};
+use crate::handlers::{auth::*, course::*, enrollment::*, general::*, student::*, tutor::*};
 use actix_web::web;
 
 pub fn general_routes(cfg: &mut web::ServiceConfig) {
-    cfg.route("/health", web::get().to(health_check_handler));
+    cfg.route("/health", web::get().to(liveness_handler))
+        .route("/health/live", web::get().to(liveness_handler))
+        .route("/health/ready", web::get().to(readiness_handler))
+        .route("/metrics", web::get().to(metrics_handler));
 }
 
 pub fn course_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/courses")
             .route("/", web::post().to(post_new_course))
+            .route("/search", web::get().to(search_courses))
             .route("/{tutor_id}", web::get().to(get_courses_for_tutor))
             .route("/{tutor_id}/{course_id}", web::get().to(get_course_details))
             .route(
                 "/{tutor_id}/{course_id}",
                 web::put().to(update_course_details),
             )
-            .route("/{tutor_id}/{course_id}", web::delete().to(delete_course)),
+            .route("/{tutor_id}/{course_id}", web::delete().to(delete_course))
+            .route(
+                "/{tutor_id}/{course_id}/roster",
+                web::get().to(get_course_roster),
+            ),
     );
 }
 
@@ -25,7 +33,32 @@ pub fn tutor_routes(cfg: &mut web::ServiceConfig) {
             .route("/", web::post().to(post_new_tutor))
             .route("/", web::get().to(get_all_tutors))
             .route("/{tutor_id}", web::get().to(get_tutor_details))
-            /* .route("/{tutor_id}", web::put().to(update_tutor_details))
-            .route("/{tutor_id}", web::delete().to(delete_tutor)) */
+            .route("/{tutor_id}", web::put().to(update_tutor_details))
+            .route("/{tutor_id}", web::delete().to(delete_tutor)),
+    );
+}
+
+pub fn student_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/students")
+            .route("/", web::post().to(post_new_student))
+            .route("/{student_id}", web::get().to(get_student_details))
+            .route(
+                "/{student_id}/enrollments",
+                web::get().to(get_enrollments_for_student),
+            )
+            .route("/{student_id}/enrollments", web::post().to(enroll_student))
+            .route(
+                "/{student_id}/enrollments/{course_id}",
+                web::delete().to(unenroll_student),
+            ),
+    );
+}
+
+pub fn auth_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/auth")
+            .route("/me", web::get().to(get_identity))
+            .route("/tokens", web::post().to(post_new_token)),
     );
 }
@@ -0,0 +1,10 @@
+use tutor_common::settings::{CommonSettings, Config, ConfigError};
+
+// The tutor web service needs nothing beyond the settings every server has
+pub type Settings = CommonSettings;
+
+pub fn load() -> Result<Settings, ConfigError> {
+    let mut config = Config::load(&[("server.bind", "127.0.0.1:3000")])?;
+    let settings = CommonSettings::read(&mut config);
+    config.finish(settings)
+}